@@ -0,0 +1,42 @@
+use aocrs::runner::{run_all, Outcome};
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Runs every solver against every input variant, e.g. `cargo run --bin variants -- input 2023`.
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let root = args.next().unwrap_or_else(|| "input".to_string());
+    let year = args
+        .next()
+        .map(|y| {
+            y.parse()
+                .unwrap_or_else(|e| panic!("Invalid year {y}: {e}"))
+        })
+        .unwrap_or(2023);
+    let runs = run_all(Path::new(&root), year)
+        .unwrap_or_else(|e| panic!("Could not read inputs from {root}: {e}"));
+    let mut failed = false;
+    for run in runs {
+        let status = match &run.outcome {
+            Outcome::Unchecked => "?".to_string(),
+            Outcome::Correct => "ok".to_string(),
+            Outcome::Incorrect { expected } => {
+                failed = true;
+                format!("FAILED, expected {expected}")
+            }
+            Outcome::Panicked { message } => {
+                failed = true;
+                format!("FAILED, panicked: {message}")
+            }
+        };
+        println!(
+            "{} part {}: {} ({status})",
+            run.variant, run.part, run.answer
+        );
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -1,39 +1,127 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalibrationError {
+    NoDigits { line: usize },
+    NotADigit { token: String, value: u32 },
+}
+
+impl std::fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalibrationError::NoDigits { line } => write!(f, "no digits on line {line}"),
+            CalibrationError::NotADigit { token, value } => {
+                write!(
+                    f,
+                    "token {token:?} stands for {value}, which is not a single digit"
+                )
+            }
+        }
+    }
+}
+
+/// The tokens that count as digits in a calibration line, and the value each stands for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vocabulary {
+    tokens: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    /// Every token must stand for a single digit, since the first and last make up a
+    /// two-digit calibration value.
+    pub fn new<'a>(
+        tokens: impl IntoIterator<Item = (&'a str, u32)>,
+    ) -> Result<Vocabulary, CalibrationError> {
+        let tokens = tokens
+            .into_iter()
+            .filter(|(token, _)| !token.is_empty())
+            .map(|(token, value)| match value {
+                0..=9 => Ok((token.to_string(), value)),
+                _ => Err(CalibrationError::NotADigit {
+                    token: token.to_string(),
+                    value,
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Vocabulary { tokens })
+    }
+
+    pub fn digits() -> Vocabulary {
+        Vocabulary::new(
+            ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+                .into_iter()
+                .zip(0..),
+        )
+        .unwrap()
+    }
+
+    pub fn with_words<'a>(
+        mut self,
+        words: impl IntoIterator<Item = (&'a str, u32)>,
+    ) -> Result<Vocabulary, CalibrationError> {
+        self.tokens.extend(Vocabulary::new(words)?.tokens);
+        Ok(self)
+    }
+
+    pub fn english() -> Vocabulary {
+        Vocabulary::digits()
+            .with_words(
+                [
+                    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+                ]
+                .into_iter()
+                .zip(1..),
+            )
+            .unwrap()
+    }
+
+    /// The longest token starting at `at`, so a longer word wins over a shorter one it starts with.
+    fn token_at(&self, line: &[u8], at: usize) -> Option<u32> {
+        self.tokens
+            .iter()
+            .filter(|(token, _)| line[at..].starts_with(token.as_bytes()))
+            .max_by_key(|(token, _)| token.len())
+            .map(|(_, value)| *value)
+    }
+
+    /// Scans in from both ends of the line, so tokens sharing letters like "eightwo" are
+    /// found without rewriting the line.
+    pub fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let line = line.as_bytes();
+        let first = (0..line.len()).find_map(|at| self.token_at(line, at))?;
+        let last = (0..line.len())
+            .rev()
+            .find_map(|at| self.token_at(line, at))?;
+        Some((first, last))
+    }
+
+    pub fn calibration_values(&self, input: &str) -> Result<Vec<u32>, CalibrationError> {
+        input
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                self.first_and_last(line)
+                    .map(|(first, last)| first * 10 + last)
+                    .ok_or(CalibrationError::NoDigits { line: i + 1 })
+            })
+            .collect()
+    }
+}
+
 #[aoc_generator(day1, part1)]
 pub fn input_generator_part_1(input: &str) -> Vec<u32> {
-    input
-        .lines()
-        .map(|line| {
-            let numbers: Vec<u32> = line.chars().filter_map(|c| c.to_digit(10)).collect();
-            numbers.first().unwrap() * 10 + numbers.last().unwrap()
-        })
-        .collect()
+    Vocabulary::digits()
+        .calibration_values(input)
+        .unwrap_or_else(|e| panic!("Invalid calibration document: {e}"))
 }
 
 #[aoc_generator(day1, part2)]
 pub fn input_generator_part_2(input: &str) -> Vec<u32> {
-    input
-        .lines()
-        .map(|line| {
-            line.to_string()
-                .replace("one", "one1one")
-                .replace("two", "two2two")
-                .replace("three", "three3three")
-                .replace("four", "four4four")
-                .replace("five", "five5five")
-                .replace("six", "six6six")
-                .replace("seven", "seven7seven")
-                .replace("eight", "eight8eight")
-                .replace("nine", "nine9nine")
-        })
-        .map(|line| {
-            line.chars()
-                .filter_map(|c| c.to_digit(10))
-                .collect::<Vec<u32>>()
-        })
-        .map(|vec| 10 * vec.first().unwrap() + vec.last().unwrap())
-        .collect()
+    Vocabulary::english()
+        .calibration_values(input)
+        .unwrap_or_else(|e| panic!("Invalid calibration document: {e}"))
 }
 
 #[aoc(day1, part1)]
@@ -72,4 +160,49 @@ treb7uchet"#;
         let input = input_generator_part_2(input);
         assert_eq!(solve_part2(&input), 281);
     }
+
+    #[test]
+    pub fn overlapping_words() {
+        let english = Vocabulary::english();
+        assert_eq!(english.first_and_last("eightwo"), Some((8, 2)));
+        assert_eq!(english.first_and_last("oneight"), Some((1, 8)));
+        assert_eq!(english.first_and_last("xtwonex"), Some((2, 1)));
+    }
+
+    #[test]
+    pub fn custom_vocabulary() {
+        let german = Vocabulary::digits()
+            .with_words([("eins", 1), ("zwei", 2), ("fünf", 5)])
+            .unwrap();
+        assert_eq!(
+            german.calibration_values("fünfabczwei\na3"),
+            Ok(vec![52, 33])
+        );
+    }
+
+    #[test]
+    pub fn reports_lines_without_digits() {
+        assert_eq!(
+            Vocabulary::digits().calibration_values("1abc2\nnone\n"),
+            Err(CalibrationError::NoDigits { line: 2 })
+        );
+    }
+
+    #[test]
+    pub fn prefers_the_longest_token() {
+        let vocabulary = Vocabulary::new([("one", 1), ("onex", 7)]).unwrap();
+        assert_eq!(vocabulary.first_and_last("onex"), Some((7, 7)));
+        assert_eq!(vocabulary.first_and_last("oney"), Some((1, 1)));
+    }
+
+    #[test]
+    pub fn rejects_multi_digit_values() {
+        assert_eq!(
+            Vocabulary::digits().with_words([("eleven", 11)]),
+            Err(CalibrationError::NotADigit {
+                token: "eleven".to_string(),
+                value: 11
+            })
+        );
+    }
 }
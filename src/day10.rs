@@ -1,12 +1,12 @@
-use std::collections::HashSet;
-
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::polygon::Polygon;
+
 pub type Grid = Vec<Vec<char>>;
 pub type Point = (usize, usize);
 
 #[aoc_generator(day10)]
-fn parse(input: &str) -> Grid {
+pub(crate) fn parse(input: &str) -> Grid {
     let mut grid: Grid = input
         .lines()
         .collect::<Vec<_>>()
@@ -16,8 +16,8 @@ fn parse(input: &str) -> Grid {
         .map(|l| ".".to_owned() + l + ".")
         .map(|l| l.chars().collect())
         .collect();
-    grid.insert(0, std::iter::repeat('.').take(grid[0].len()).collect());
-    grid.push(std::iter::repeat('.').take(grid[0].len()).collect());
+    grid.insert(0, std::iter::repeat_n('.', grid[0].len()).collect());
+    grid.push(std::iter::repeat_n('.', grid[0].len()).collect());
     grid
 }
 
@@ -68,15 +68,11 @@ fn find_pipes(grid: &Grid) -> Vec<Point> {
         if n == s {
             continue;
         }
-        let connections = connections(grid, n);
-        match connections {
-            Some((a, b)) => {
-                if a == curr || b == curr {
-                    curr = n;
-                    break;
-                }
+        if let Some((a, b)) = connections(grid, n) {
+            if a == curr || b == curr {
+                curr = n;
+                break;
             }
-            None => {}
         }
     }
     let mut pipes = vec![s];
@@ -89,83 +85,25 @@ fn find_pipes(grid: &Grid) -> Vec<Point> {
     pipes
 }
 
-fn count_symbol_occurrence(grid: &Grid, symbol: char) -> usize {
-    grid.iter()
-        .map(|row| row.iter().filter(|c| **c == symbol).count())
-        .sum()
-}
-
-fn search_and_mark(grid: Grid, curr: Point, pipes: &HashSet<Point>) -> Grid {
-    if curr.1 >= grid.len() || curr.0 >= grid[0].len() {
-        return grid;
-    }
-    if grid[curr.1][curr.0] == 'X' {
-        return grid;
-    }
-    if pipes.contains(&curr) {
-        return grid;
-    }
-    let neighbours = cardinal_neighbours(curr);
-    let mut g = grid;
-    g[curr.1][curr.0] = 'X';
-    for n in neighbours {
-        g = search_and_mark(g, n, pipes)
-    }
-    g
-}
-
-fn mark_grid(grid: Grid) -> (Grid, HashSet<Point>) {
-    let starting_point = find_starting_pos(&grid);
-    let pipes = find_pipes(&grid);
-    let pipe_set: HashSet<_> = pipes.iter().cloned().collect();
-    let mut marked_grid = grid;
-    let mut previous = (starting_point.0 as i64, starting_point.1 as i64);
-    let mut points_to_mark = vec![];
-    for segment in pipes {
-        let curr = (segment.0 as i64, segment.1 as i64);
-        match (curr.0 - previous.0, curr.1 - previous.1) {
-            (1, 0) => {
-                points_to_mark.push((segment.0, segment.1 + 1));
-                points_to_mark.push((segment.0.wrapping_sub(1), segment.1 + 1));
-            }
-            (0, 1) => {
-                points_to_mark.push((segment.0.wrapping_sub(1), segment.1.wrapping_sub(1)));
-                points_to_mark.push((segment.0.wrapping_sub(1), segment.1));
-            }
-            (-1, 0) => {
-                points_to_mark.push((segment.0, segment.1.wrapping_sub(1)));
-                points_to_mark.push((segment.0 + 1, segment.1.wrapping_sub(1)));
-            }
-            (0, -1) => {
-                points_to_mark.push((segment.0 + 1, segment.1));
-                points_to_mark.push((segment.0 + 1, segment.1 + 1));
-            }
-            _ => {}
-        }
-        previous = curr;
-    }
-    for p in points_to_mark {
-        marked_grid = search_and_mark(marked_grid, p, &pipe_set);
-    }
-    (marked_grid, pipe_set)
+fn pipe_loop(grid: &Grid) -> Polygon {
+    Polygon::new(
+        find_pipes(grid)
+            .into_iter()
+            .map(|(x, y)| (x as i64, y as i64))
+            .collect(),
+    )
 }
 
 #[aoc(day10, part1)]
-fn part1(input: &Grid) -> usize {
+pub(crate) fn part1(input: &Grid) -> usize {
     let pipes = find_pipes(input);
     pipes.len() / 2
 }
 
 #[aoc(day10, part2)]
-fn part2(input: &Grid) -> usize {
-    let (marked_grid, pipe_set) = mark_grid(input.clone());
-    let num_marked = count_symbol_occurrence(&marked_grid, 'X');
-    if marked_grid[0][0] == 'X' {
-        let total = marked_grid.len() * marked_grid[0].len();
-        total - num_marked - pipe_set.len()
-    } else {
-        num_marked
-    }
+pub(crate) fn part2(input: &Grid) -> usize {
+    // A loop that encloses no area has nothing inside it
+    pipe_loop(input).interior_points().unwrap_or(0) as usize
 }
 
 #[cfg(test)]
@@ -194,18 +132,8 @@ L7JLJL-JLJLJL--JLJ.L"#;
         assert_eq!(part1(&parse(SMALL_TEST)), 8);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(include_str!("../input/2023/day10.txt"))), 6717);
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(PART2_EXAMPLE)), 10);
     }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(part2(&parse(include_str!("../input/2023/day10.txt"))), 381);
-    }
 }
@@ -1,81 +1,118 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::num::NonZeroUsize;
 pub type Point = (usize, usize);
-#[aoc_generator(day11, part1)]
-fn parse(input: &str) -> (Vec<Point>, Vec<usize>) {
-    let mut coords = Vec::new();
-    let mut empty_cols = Vec::from_iter(0..input.lines().next().unwrap().len());
-    let mut row_offset = 0;
-    for (i, line) in input.lines().enumerate() {
-        let mut empty = true;
-        for (j, c) in line.chars().enumerate() {
-            if c == '#' {
-                coords.push((i + row_offset, j));
-                empty_cols.retain(|&x| x != j);
-                empty = false;
+
+fn factor(n: usize) -> NonZeroUsize {
+    NonZeroUsize::new(n).expect("expansion factor must be non-zero")
+}
+
+/// Galaxy positions as observed, before any cosmic expansion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Universe {
+    pub galaxies: Vec<Point>,
+    empty_rows: Vec<usize>,
+    empty_cols: Vec<usize>,
+}
+
+impl Universe {
+    /// Galaxy positions after every empty row and column has grown to `factor` copies of itself.
+    pub fn expand(&self, factor: NonZeroUsize) -> Vec<Point> {
+        let grow =
+            |empty: &[usize], n: usize| n + empty.partition_point(|&e| e < n) * (factor.get() - 1);
+        self.galaxies
+            .iter()
+            .map(|&(i, j)| (grow(&self.empty_rows, i), grow(&self.empty_cols, j)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UniverseError {
+    RaggedRow { row: usize, width: usize },
+    InvalidCell(char),
+}
+
+impl std::fmt::Display for UniverseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniverseError::RaggedRow { row, width } => {
+                write!(f, "row {row} is not {width} cells wide")
             }
+            UniverseError::InvalidCell(c) => write!(f, "invalid cell {c:?}"),
         }
-        row_offset += empty as usize;
     }
-    (coords, empty_cols)
 }
 
-#[aoc_generator(day11, part2)]
-fn parse_with_ancient_expansion(input: &str) -> (Vec<Point>, Vec<usize>) {
-    let mut coords = Vec::new();
-    let mut empty_cols = Vec::from_iter(0..input.lines().next().unwrap().len());
-    let mut row_offset = 0;
-    let expansion: usize = 1_000_000 - 1;
-    for (i, line) in input.lines().enumerate() {
-        let mut empty = true;
-        for (j, c) in line.chars().enumerate() {
-            if c == '#' {
-                coords.push((i + row_offset, j));
-                empty_cols.retain(|&x| x != j);
-                empty = false;
+impl Universe {
+    pub fn parse(input: &str) -> Result<Universe, UniverseError> {
+        let lines: Vec<&str> = input.lines().filter(|l| !l.is_empty()).collect();
+        let width = lines.first().map_or(0, |l| l.len());
+        let mut galaxies = vec![];
+        for (i, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(UniverseError::RaggedRow { row: i, width });
+            }
+            for (j, c) in line.char_indices() {
+                match c {
+                    '#' => galaxies.push((i, j)),
+                    '.' => {}
+                    c => return Err(UniverseError::InvalidCell(c)),
+                }
             }
         }
-        row_offset += empty as usize * expansion;
+        let mut occupied_rows = vec![false; lines.len()];
+        let mut occupied_cols = vec![false; width];
+        for &(i, j) in &galaxies {
+            occupied_rows[i] = true;
+            occupied_cols[j] = true;
+        }
+        let empty = |occupied: Vec<bool>| {
+            occupied
+                .iter()
+                .enumerate()
+                .filter(|(_, o)| !**o)
+                .map(|(n, _)| n)
+                .collect()
+        };
+        Ok(Universe {
+            galaxies,
+            empty_rows: empty(occupied_rows),
+            empty_cols: empty(occupied_cols),
+        })
     }
-    (coords, empty_cols)
 }
 
-fn expand(mut coords: Vec<Point>, empty_cols: Vec<usize>, expansion: usize) -> Vec<Point> {
-    for empty_col in empty_cols.iter().rev() {
-        coords.iter_mut().for_each(|(_, j)| {
-            if *j > *empty_col {
-                *j += expansion;
-            }
-        });
+#[aoc_generator(day11)]
+pub(crate) fn parse(input: &str) -> Universe {
+    Universe::parse(input).unwrap_or_else(|e| panic!("Invalid image: {e}"))
+}
+
+/// Sum of |a - b| over all pairs. Once sorted, the k-th value is subtracted from by
+/// the k values before it, which a running prefix sum accounts for in one pass.
+fn pairwise_distance_sum(mut values: Vec<usize>) -> usize {
+    values.sort_unstable();
+    let mut prefix = 0;
+    let mut total = 0;
+    for (k, v) in values.into_iter().enumerate() {
+        total += k * v - prefix;
+        prefix += v;
     }
-    coords
+    total
 }
 
-fn shortest_distance_sum(coords: Vec<Point>) -> usize {
-    let mut n = 0;
-    (0..coords.len()).for_each(|left| {
-        (left + 1..coords.len()).for_each(|right| {
-            let left = coords[left];
-            let right = coords[right];
-
-            let max_i = left.0.max(right.0);
-            let min_i = left.0.min(right.0);
-            let max_j = left.1.max(right.1);
-            let min_j = left.1.min(right.1);
-            n += max_i - min_i + max_j - min_j
-        })
-    });
-    n
+fn shortest_distance_sum(coords: &[Point]) -> usize {
+    pairwise_distance_sum(coords.iter().map(|p| p.0).collect())
+        + pairwise_distance_sum(coords.iter().map(|p| p.1).collect())
 }
+
 #[aoc(day11, part1)]
-fn part1(input: &(Vec<Point>, Vec<usize>)) -> usize {
-    let coords = expand(input.0.clone(), input.1.clone(), 1);
-    shortest_distance_sum(coords)
+pub(crate) fn part1(input: &Universe) -> usize {
+    shortest_distance_sum(&input.expand(factor(2)))
 }
 
 #[aoc(day11, part2)]
-fn part2(input: &(Vec<Point>, Vec<usize>)) -> usize {
-    let coords = expand(input.0.clone(), input.1.clone(), 999_999);
-    shortest_distance_sum(coords)
+pub(crate) fn part2(input: &Universe) -> usize {
+    shortest_distance_sum(&input.expand(factor(1_000_000)))
 }
 
 #[cfg(test)]
@@ -99,25 +136,27 @@ mod tests {
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(
-            part1(&parse(include_str!("../input/2023/day11.txt"))),
-            10313550
-        );
+    fn part2_example() {
+        assert_eq!(part2(&parse(EXAMPLE)), 82000210);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse_with_ancient_expansion(EXAMPLE)), 82000210);
+    fn expands_by_any_factor() {
+        let universe = parse(EXAMPLE);
+        assert_eq!(shortest_distance_sum(&universe.expand(factor(10))), 1030);
+        assert_eq!(shortest_distance_sum(&universe.expand(factor(100))), 8410);
+        assert_eq!(universe.expand(factor(1)), universe.galaxies);
     }
 
     #[test]
-    fn solves_part2() {
+    fn rejects_ragged_images() {
+        assert_eq!(
+            Universe::parse("#.\n..#"),
+            Err(UniverseError::RaggedRow { row: 1, width: 2 })
+        );
         assert_eq!(
-            part2(&parse_with_ancient_expansion(include_str!(
-                "../input/2023/day11.txt"
-            ))),
-            611998089572
+            Universe::parse("#.\n.*"),
+            Err(UniverseError::InvalidCell('*'))
         );
     }
 }
@@ -2,7 +2,7 @@ use aoc_runner_derive::{aoc, aoc_generator};
 
 pub type Springs = (String, Vec<usize>);
 #[aoc_generator(day12)]
-fn parse(input: &str) -> Vec<Springs> {
+pub(crate) fn parse(input: &str) -> Vec<Springs> {
     input
         .lines()
         .map(|line| {
@@ -50,7 +50,7 @@ fn count_arrangements(pattern: &str, counts: &[usize]) -> usize {
 }
 
 #[aoc(day12, part1)]
-fn part1(input: &[Springs]) -> usize {
+pub(crate) fn part1(input: &[Springs]) -> usize {
     input
         .iter()
         .map(|(pattern, counts)| count_arrangements(pattern, counts))
@@ -58,7 +58,7 @@ fn part1(input: &[Springs]) -> usize {
 }
 
 #[aoc(day12, part2)]
-fn part2(input: &[Springs]) -> usize {
+pub(crate) fn part2(input: &[Springs]) -> usize {
     input
         .iter()
         .map(|(p, c)| (p.as_str(), c))
@@ -86,21 +86,8 @@ mod tests {
         assert_eq!(part1(&parse(EXAMPLE)), 21);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(include_str!("../input/2023/day12.txt"))), 7204);
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 525152);
     }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(
-            part2(&parse(include_str!("../input/2023/day12.txt"))),
-            1672318386674
-        )
-    }
 }
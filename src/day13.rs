@@ -1,113 +1,189 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-pub type Grid = (Vec<u64>, Vec<u64>);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MirrorError {
+    Empty,
+    InvalidCell(char),
+    RaggedRow(usize),
+    NoReflection { smudges: usize },
+}
 
-#[aoc_generator(day13)]
-fn parse(input: &str) -> Vec<Grid> {
-    input
-        .split("\n\n")
-        .map(|block| {
-            let lines: Vec<_> = block.lines().collect();
-            let rows: Vec<u64> = lines
-                .iter()
-                .map(|line| {
-                    line.chars().fold(0u64, |mut row, c| {
-                        row <<= 1;
-                        row |= (c == '#') as u64;
-                        row
+impl std::fmt::Display for MirrorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MirrorError::Empty => write!(f, "pattern has no rows"),
+            MirrorError::InvalidCell(c) => write!(f, "invalid cell {c:?}"),
+            MirrorError::RaggedRow(row) => write!(f, "row {row} has a different width"),
+            MirrorError::NoReflection { smudges } => {
+                write!(f, "no reflection with exactly {smudges} smudges")
+            }
+        }
+    }
+}
+
+/// A fixed-length line of cells, packed 64 to a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitLine {
+    words: Vec<u64>,
+}
+
+impl BitLine {
+    fn new(len: usize) -> BitLine {
+        BitLine {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn set(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    /// Positions where the two lines differ, in increasing order.
+    fn differences<'a>(&'a self, other: &'a BitLine) -> impl Iterator<Item = usize> + 'a {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .enumerate()
+            .flat_map(|(w, (a, b))| {
+                let mut diff = a ^ b;
+                std::iter::from_fn(move || {
+                    (diff != 0).then(|| {
+                        let bit = diff.trailing_zeros() as usize;
+                        diff &= diff - 1;
+                        w * 64 + bit
                     })
                 })
-                .collect();
-            let mut cols = vec![0u64; lines[0].len()];
-            for &row in rows.iter() {
-                for (j, col) in cols.iter_mut().enumerate() {
-                    *col <<= 1;
-                    *col |= (row >> (lines[0].len() - j - 1)) & 1;
-                }
-            }
-            (rows, cols)
-        })
-        .collect()
+            })
+    }
 }
 
-fn find_reflection(values: &[u64]) -> Option<usize> {
-    'OUTER: for (i, pair) in values.iter().zip(values.iter().skip(1)).enumerate() {
-        if pair.0 != pair.1 {
-            continue;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    /// Between rows, with this many rows above it.
+    Horizontal(usize),
+    /// Between columns, with this many columns left of it.
+    Vertical(usize),
+}
+
+impl Axis {
+    pub fn summary(&self) -> usize {
+        match self {
+            Axis::Horizontal(rows) => 100 * rows,
+            Axis::Vertical(cols) => *cols,
         }
-        let mut k = 1;
-        while i as i32 - k >= 0 && i as i32 + k + 1 < values.len() as i32 {
-            if values[i - k as usize] != values[i + k as usize + 1] {
-                continue 'OUTER;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    /// The `(row, col)` cells on the top or left side that must flip for the reflection to be exact.
+    pub smudges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    rows: Vec<BitLine>,
+    cols: Vec<BitLine>,
+}
+
+impl Pattern {
+    pub fn parse(block: &str) -> Result<Pattern, MirrorError> {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.is_empty()).collect();
+        let width = lines.first().ok_or(MirrorError::Empty)?.len();
+        let mut rows = vec![BitLine::new(width); lines.len()];
+        let mut cols = vec![BitLine::new(lines.len()); width];
+        for (i, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(MirrorError::RaggedRow(i));
+            }
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    '#' => {
+                        rows[i].set(j);
+                        cols[j].set(i);
+                    }
+                    '.' => {}
+                    c => return Err(MirrorError::InvalidCell(c)),
+                }
             }
-            k += 1;
         }
-        return Some(i);
+        Ok(Pattern { rows, cols })
+    }
+
+    /// Every axis that reflects the pattern once at most `max_smudges` cells are fixed,
+    /// horizontal axes first.
+    pub fn find_reflection(&self, max_smudges: usize) -> Vec<Reflection> {
+        let horizontal = reflections(&self.rows, max_smudges).map(|(rows, smudges)| Reflection {
+            axis: Axis::Horizontal(rows),
+            smudges,
+        });
+        let vertical = reflections(&self.cols, max_smudges).map(|(cols, smudges)| Reflection {
+            axis: Axis::Vertical(cols),
+            smudges: smudges.into_iter().map(|(col, row)| (row, col)).collect(),
+        });
+        horizontal.chain(vertical).collect()
+    }
+
+    /// The first axis needing exactly `smudges` fixes.
+    pub fn reflection_with(&self, smudges: usize) -> Result<Axis, MirrorError> {
+        self.find_reflection(smudges)
+            .into_iter()
+            .find(|r| r.smudges.len() == smudges)
+            .map(|r| r.axis)
+            .ok_or(MirrorError::NoReflection { smudges })
     }
-    None
 }
 
-fn smudged_reflection(values: &[u64]) -> Option<usize> {
-    'OUTER: for (i, pair) in values.iter().zip(values.iter().skip(1)).enumerate() {
-        let mut smudged = false;
-        if pair.0 != pair.1 && (pair.0 ^ pair.1).count_ones() != 1 {
-            continue;
-        } else if pair.0 != pair.1 {
-            smudged = true;
-        }
-        let mut k = 1;
-        while i as i32 - k >= 0 && i as i32 + k + 1 < values.len() as i32 {
-            let left = values[i - k as usize];
-            let right = values[i + k as usize + 1];
-            if left != right && (left ^ right).count_ones() != 1 {
-                continue 'OUTER;
-            } else if left != right {
-                if smudged {
-                    continue 'OUTER;
+/// Axes between `lines` as `(lines before axis, smudges as (line, position))`.
+fn reflections(
+    lines: &[BitLine],
+    max_smudges: usize,
+) -> impl Iterator<Item = (usize, Vec<(usize, usize)>)> + '_ {
+    (1..lines.len()).filter_map(move |axis| {
+        let mut smudges = vec![];
+        for (before, after) in (0..axis).rev().zip(axis..lines.len()) {
+            for pos in lines[before].differences(&lines[after]) {
+                if smudges.len() == max_smudges {
+                    return None;
                 }
-                smudged = true;
+                smudges.push((before, pos));
             }
-            k += 1;
         }
-        if smudged {
-            return Some(i);
-        }
-    }
-    None
+        Some((axis, smudges))
+    })
 }
 
-#[aoc(day13, part1)]
-fn part1(input: &[Grid]) -> usize {
+#[aoc_generator(day13)]
+pub(crate) fn parse(input: &str) -> Vec<Pattern> {
     input
-        .iter()
-        .map(|(rows, cols)| {
-            if let Some(row_reflection) = find_reflection(rows) {
-                100 * (row_reflection + 1)
-            } else if let Some(col_reflection) = find_reflection(cols) {
-                col_reflection + 1
-            } else {
-                unreachable!("No reflection found");
-            }
-        })
-        .sum()
+        .split("\n\n")
+        .map(|block| Pattern::parse(block).unwrap_or_else(|e| panic!("Invalid pattern: {e}")))
+        .collect()
 }
 
-#[aoc(day13, part2)]
-fn part2(input: &[Grid]) -> usize {
+fn summarize(input: &[Pattern], smudges: usize) -> usize {
     input
         .iter()
-        .map(|(rows, cols)| {
-            if let Some(row_reflection) = smudged_reflection(rows) {
-                100 * (row_reflection + 1)
-            } else if let Some(col_reflection) = smudged_reflection(cols) {
-                col_reflection + 1
-            } else {
-                unreachable!("No reflection found");
-            }
+        .map(|pattern| {
+            pattern
+                .reflection_with(smudges)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .summary()
         })
         .sum()
 }
 
+#[aoc(day13, part1)]
+pub(crate) fn part1(input: &[Pattern]) -> usize {
+    summarize(input, 0)
+}
+
+#[aoc(day13, part2)]
+pub(crate) fn part2(input: &[Pattern]) -> usize {
+    summarize(input, 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,14 +209,6 @@ mod tests {
         assert_eq!(part1(&parse(SECOND)), 400);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(
-            part1(&parse(include_str!("../input/2023/day13.txt"))),
-            39939
-        );
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(FIRST)), 300);
@@ -148,10 +216,39 @@ mod tests {
     }
 
     #[test]
-    fn solves_part2() {
+    fn reports_smudge_positions() {
+        let pattern = Pattern::parse(FIRST).unwrap();
         assert_eq!(
-            part2(&parse(include_str!("../input/2023/day13.txt"))),
-            32069
+            pattern.find_reflection(0),
+            vec![Reflection {
+                axis: Axis::Vertical(5),
+                smudges: vec![]
+            }]
+        );
+        let smudged = pattern.find_reflection(1);
+        assert!(smudged.contains(&Reflection {
+            axis: Axis::Horizontal(3),
+            smudges: vec![(0, 0)]
+        }));
+        assert_eq!(
+            Pattern::parse("#.\n.#").unwrap().reflection_with(0),
+            Err(MirrorError::NoReflection { smudges: 0 })
+        );
+    }
+
+    #[test]
+    fn handles_patterns_wider_than_64() {
+        let row = format!("{}#.{}", ".".repeat(69), ".#".repeat(35));
+        let pattern = Pattern::parse(&format!("{row}\n{row}")).unwrap();
+        assert_eq!(pattern.reflection_with(0), Ok(Axis::Horizontal(1)));
+        let smudged = row.replacen('#', ".", 1);
+        let pattern = Pattern::parse(&format!("{row}\n{smudged}")).unwrap();
+        assert_eq!(
+            pattern.find_reflection(1)[0],
+            Reflection {
+                axis: Axis::Horizontal(1),
+                smudges: vec![(0, 69)]
+            }
         );
     }
 }
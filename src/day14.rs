@@ -4,7 +4,7 @@ use aoc_runner_derive::{aoc, aoc_generator};
 pub type Board = Vec<Vec<u8>>;
 
 #[aoc_generator(day14)]
-fn parse(input: &str) -> Board {
+pub(crate) fn parse(input: &str) -> Board {
     input
         .lines()
         .map(|line| line.as_bytes().to_vec())
@@ -51,7 +51,7 @@ fn load(board: &Board) -> usize {
 }
 
 #[aoc(day14, part1)]
-fn part1(input: &Board) -> usize {
+pub(crate) fn part1(input: &Board) -> usize {
     let mut mine = input.clone();
     tilt_north(&mut mine);
     assert_ne!(&mine, input);
@@ -59,7 +59,7 @@ fn part1(input: &Board) -> usize {
 }
 
 #[aoc(day14, part2)]
-fn part2(input: &Board) -> usize {
+pub(crate) fn part2(input: &Board) -> usize {
     let mut mine = input.clone();
     let mut seen = HashMap::new();
     for i in 1..1_000_000_000 {
@@ -95,24 +95,8 @@ O.#..O.#.#
         assert_eq!(part1(&parse(EXAMPLE)), 136);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(
-            part1(&parse(include_str!("../input/2023/day14.txt"))),
-            109385
-        );
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 64);
     }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(
-            part2(&parse(include_str!("../input/2023/day14.txt"))),
-            93102
-        );
-    }
 }
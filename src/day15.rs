@@ -1,68 +1,284 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
 #[aoc_generator(day15)]
-fn parse(input: &str) -> Vec<String> {
+pub(crate) fn parse(input: &str) -> Vec<String> {
     input.split(',').map(|f| f.to_owned()).collect()
 }
 
+/// The HASH algorithm over bytes: add each byte, multiply, then reduce by the modulus.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Hash256 {
+    multiplier: u64,
+    modulus: u64,
+}
+
+/// The parameters from the puzzle.
+pub const HASH: Hash256 = Hash256 {
+    multiplier: 17,
+    modulus: 256,
+};
+
+/// Steps hashed side by side in the batch path, one per lane.
+const LANES: usize = 16;
+
+impl Hash256 {
+    pub fn new(multiplier: u32, modulus: NonZeroU32) -> Hash256 {
+        let modulus = modulus.get() as u64;
+        Hash256 {
+            multiplier: multiplier as u64 % modulus,
+            modulus,
+        }
+    }
+
+    /// One step of the fold. Both factors are below the modulus, so the product fits in a u64.
+    #[inline(always)]
+    fn step(&self, acc: u64, byte: u64) -> u64 {
+        let reduce = |n: u64| {
+            if self.modulus.is_power_of_two() {
+                n & (self.modulus - 1)
+            } else {
+                n % self.modulus
+            }
+        };
+        reduce(reduce(acc + byte) * self.multiplier)
+    }
+
+    pub fn hash(&self, bytes: &[u8]) -> u32 {
+        bytes.iter().fold(0, |acc, &b| self.step(acc, b as u64)) as u32
+    }
+
+    /// Hashes every step. With a power-of-two modulus, wrapping arithmetic agrees with the
+    /// modulus at every step, so [`LANES`] steps are folded side by side in u32 lanes and only
+    /// reduced at the end. Other moduli fall back to hashing each step on its own.
+    pub fn hash_all(&self, steps: &[&[u8]]) -> Vec<u32> {
+        if !self.modulus.is_power_of_two() || self.modulus > 1 << 32 {
+            return steps.iter().map(|step| self.hash(step)).collect();
+        }
+        let mask = (self.modulus - 1) as u32;
+        let multiplier = self.multiplier as u32;
+        let mut hashes = Vec::with_capacity(steps.len());
+        for chunk in steps.chunks(LANES) {
+            let acc = fold_lanes(chunk, multiplier);
+            hashes.extend(acc[..chunk.len()].iter().map(|&h| h & mask));
+        }
+        hashes
+    }
+
+    /// Hashes each comma-separated step of an initialization sequence, ignoring newlines.
+    pub fn hash_steps(&self, sequence: &str) -> Vec<u32> {
+        let steps: Vec<&[u8]> = sequence
+            .trim_end_matches('\n')
+            .split(',')
+            .map(|step| step.trim_matches('\n').as_bytes())
+            .collect();
+        self.hash_all(&steps)
+    }
+}
+
+/// Folds up to [`LANES`] steps at once without reducing. The bytes are first transposed into
+/// zero-padded columns, so the inner loop is the same arithmetic on every lane, with finished
+/// lanes kept by a select rather than a branch.
+fn fold_lanes(steps: &[&[u8]], multiplier: u32) -> [u32; LANES] {
+    let mut lens = [0u32; LANES];
+    for (len, step) in lens.iter_mut().zip(steps) {
+        *len = step.len() as u32;
+    }
+    let longest = lens.iter().copied().max().unwrap_or(0);
+    let mut columns = vec![[0u32; LANES]; longest as usize];
+    for (lane, step) in steps.iter().enumerate() {
+        for (column, &b) in columns.iter_mut().zip(step.iter()) {
+            column[lane] = b as u32;
+        }
+    }
+    let mut acc = [0u32; LANES];
+    for (i, column) in (0..longest).zip(&columns) {
+        for lane in 0..LANES {
+            let next = acc[lane]
+                .wrapping_add(column[lane])
+                .wrapping_mul(multiplier);
+            acc[lane] = if i < lens[lane] { next } else { acc[lane] };
+        }
+    }
+    acc
+}
+
 fn hash(input: &str) -> usize {
-    input
-        .chars()
-        .fold(0, |acc, c| ((acc + c as usize) * 17) % 256)
+    HASH.hash(input.as_bytes()) as usize
 }
 
 #[aoc(day15, part1)]
-fn part1(input: &[String]) -> usize {
-    input.iter().map(|f| hash(f.as_str())).sum()
+pub(crate) fn part1(input: &[String]) -> usize {
+    let steps: Vec<&[u8]> = input.iter().map(|s| s.as_bytes()).collect();
+    HASH.hash_all(&steps).into_iter().map(|h| h as usize).sum()
 }
 
-#[aoc(day15, part2)]
-fn part2(input: &[String]) -> usize {
-    let mut boxes: Vec<Vec<(String, usize)>> = vec![vec![]; 256];
-    for step in input {
-        if step.contains('-') {
-            let label = step.replace('-', "");
-            let n = hash(&label);
-            let new_box = boxes[n]
-                .iter()
-                .filter(|(l, _)| l != &label)
-                .cloned()
-                .collect::<Vec<_>>();
-            boxes[n] = new_box;
-        } else {
-            let label_and_focus = step.split('=').collect::<Vec<_>>();
-            let label = label_and_focus[0];
-            let n = hash(label);
-            let focal_length = label_and_focus[1].parse::<usize>().unwrap();
-            let existing = boxes[n].iter().any(|(l, _)| l == label);
-            if existing {
-                let new_box = boxes[n]
-                    .iter()
-                    .map(|pair| {
-                        if pair.0 == label {
-                            (label.to_string(), focal_length)
-                        } else {
-                            pair.clone()
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                boxes[n] = new_box;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepError {
+    EmptyLabel,
+    InvalidLabel(char),
+    MissingOperation,
+    InvalidFocalLength(String),
+    TrailingInput(String),
+}
+
+impl std::fmt::Display for StepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepError::EmptyLabel => write!(f, "step has no label"),
+            StepError::InvalidLabel(c) => write!(f, "invalid label character {c:?}"),
+            StepError::MissingOperation => write!(f, "step has no '-' or '=' operation"),
+            StepError::InvalidFocalLength(s) => write!(f, "invalid focal length {s:?}"),
+            StepError::TrailingInput(s) => write!(f, "unexpected {s:?} after removal"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Remove,
+    Insert(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub label: String,
+    pub operation: Operation,
+}
+
+impl Step {
+    /// Parses `label-` or `label=focal_length`, where the label is ASCII letters.
+    pub fn parse(step: &str) -> Result<Step, StepError> {
+        let split = step
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .ok_or(if step.is_empty() {
+                StepError::EmptyLabel
             } else {
-                boxes[n].push((label.to_owned(), focal_length));
+                StepError::MissingOperation
+            })?;
+        let (label, rest) = step.split_at(split);
+        let mut chars = rest.chars();
+        let operation = match chars.next() {
+            Some('-') if chars.as_str().is_empty() => Operation::Remove,
+            Some('-') => return Err(StepError::TrailingInput(chars.as_str().to_owned())),
+            Some('=') => {
+                let focal_length = chars.as_str();
+                if focal_length.is_empty() || !focal_length.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(StepError::InvalidFocalLength(focal_length.to_owned()));
+                }
+                Operation::Insert(
+                    focal_length
+                        .parse()
+                        .map_err(|_| StepError::InvalidFocalLength(focal_length.to_owned()))?,
+                )
+            }
+            Some(c) => return Err(StepError::InvalidLabel(c)),
+            None => unreachable!("split at a non-letter"),
+        };
+        if label.is_empty() {
+            return Err(StepError::EmptyLabel);
+        }
+        Ok(Step {
+            label: label.to_owned(),
+            operation,
+        })
+    }
+}
+
+/// One box of lenses in slot order. Removed lenses leave a hole that is compacted away
+/// once holes make up half the slots, keeping every operation amortized O(1).
+#[derive(Debug, Clone, Default)]
+struct LensBox {
+    slots: Vec<Option<(String, usize)>>,
+    index: HashMap<String, usize>,
+    holes: usize,
+}
+
+impl LensBox {
+    fn insert(&mut self, label: &str, focal_length: usize) {
+        match self.index.get(label) {
+            Some(&slot) => {
+                if let Some(lens) = self.slots[slot].as_mut() {
+                    lens.1 = focal_length;
+                }
+            }
+            None => {
+                self.index.insert(label.to_owned(), self.slots.len());
+                self.slots.push(Some((label.to_owned(), focal_length)));
             }
         }
     }
-    boxes
-        .iter()
-        .enumerate()
-        .fold(0_usize, |total, (box_idx, lens_box)| {
-            total
-                + lens_box
-                    .iter()
+
+    fn remove(&mut self, label: &str) {
+        if let Some(slot) = self.index.remove(label) {
+            self.slots[slot] = None;
+            self.holes += 1;
+            if self.holes * 2 > self.slots.len() {
+                self.compact();
+            }
+        }
+    }
+
+    fn compact(&mut self) {
+        self.slots.retain(Option::is_some);
+        for (slot, lens) in self.slots.iter().flatten().enumerate() {
+            self.index.insert(lens.0.clone(), slot);
+        }
+        self.holes = 0;
+    }
+
+    fn lenses(&self) -> impl Iterator<Item = &(String, usize)> {
+        self.slots.iter().flatten()
+    }
+}
+
+/// The 256 boxes of the HASHMAP procedure, each keeping its lenses in insertion order.
+#[derive(Debug, Clone)]
+pub struct LensBoxes {
+    boxes: Vec<LensBox>,
+}
+
+impl Default for LensBoxes {
+    fn default() -> Self {
+        LensBoxes {
+            boxes: vec![LensBox::default(); 256],
+        }
+    }
+}
+
+impl LensBoxes {
+    pub fn apply(&mut self, step: &Step) {
+        let lens_box = &mut self.boxes[hash(&step.label)];
+        match step.operation {
+            Operation::Remove => lens_box.remove(&step.label),
+            Operation::Insert(focal_length) => lens_box.insert(&step.label, focal_length),
+        }
+    }
+
+    /// Each lens in box then slot order, with its focusing power.
+    pub fn focusing_powers(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(box_idx, lens_box)| {
+                lens_box
+                    .lenses()
                     .enumerate()
-                    .fold(0_usize, |acc, (slot, (_, focal_length))| {
-                        acc + (box_idx + 1) * (slot + 1) * focal_length
+                    .map(move |(slot, (label, focal_length))| {
+                        (label.as_str(), (box_idx + 1) * (slot + 1) * focal_length)
                     })
-        })
+            })
+    }
+}
+
+#[aoc(day15, part2)]
+pub(crate) fn part2(input: &[String]) -> usize {
+    let mut boxes = LensBoxes::default();
+    for step in input {
+        boxes.apply(&Step::parse(step).unwrap_or_else(|e| panic!("Invalid step {step:?}: {e}")));
+    }
+    boxes.focusing_powers().map(|(_, power)| power).sum()
 }
 
 #[cfg(test)]
@@ -82,23 +298,70 @@ mod tests {
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(
-            part1(&parse(include_str!("../input/2023/day15.txt").trim())),
-            511215
-        );
+    fn part2_example() {
+        assert_eq!(part2(&parse(EXAMPLE)), 145);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse(EXAMPLE)), 145);
+    fn rejects_malformed_steps() {
+        assert_eq!(
+            Step::parse("qp=3"),
+            Ok(Step {
+                label: "qp".to_string(),
+                operation: Operation::Insert(3)
+            })
+        );
+        assert_eq!(Step::parse("=3"), Err(StepError::EmptyLabel));
+        assert_eq!(Step::parse("qp"), Err(StepError::MissingOperation));
+        assert_eq!(
+            Step::parse("qp="),
+            Err(StepError::InvalidFocalLength(String::new()))
+        );
+        assert_eq!(
+            Step::parse("qp-3"),
+            Err(StepError::TrailingInput("3".to_string()))
+        );
+        assert_eq!(Step::parse("q.p-"), Err(StepError::InvalidLabel('.')));
     }
 
     #[test]
-    fn solves_part2() {
+    fn keeps_order_through_removals() {
+        let mut boxes = LensBoxes::default();
+        for step in parse(EXAMPLE) {
+            boxes.apply(&Step::parse(&step).unwrap());
+        }
         assert_eq!(
-            part2(&parse(include_str!("../input/2023/day15.txt").trim())),
-            236057
+            boxes.focusing_powers().collect::<Vec<_>>(),
+            vec![("rn", 1), ("cm", 4), ("ot", 28), ("ab", 40), ("pc", 72)]
         );
     }
+
+    #[test]
+    fn batch_matches_scalar() {
+        let input = include_str!("../input/2023/day15.txt");
+        let batch = HASH.hash_steps(input);
+        let scalar: Vec<u32> = parse(input.trim())
+            .iter()
+            .map(|step| HASH.hash(step.as_bytes()))
+            .collect();
+        assert_eq!(batch, scalar);
+        assert_eq!(HASH, Hash256::new(17, NonZeroU32::new(256).unwrap()));
+
+        let steps: Vec<&[u8]> = input.trim().split(',').map(str::as_bytes).collect();
+        for hasher in [
+            Hash256::new(1_000_003, NonZeroU32::new(u32::MAX).unwrap()),
+            Hash256::new(1_000_003, NonZeroU32::new(1 << 20).unwrap()),
+            Hash256::new(u32::MAX, NonZeroU32::new(1 << 31).unwrap()),
+        ] {
+            let scalar: Vec<u32> = steps.iter().map(|s| hasher.hash(s)).collect();
+            assert_eq!(hasher.hash_all(&steps), scalar);
+        }
+    }
+
+    #[test]
+    fn configurable_parameters() {
+        let hasher = Hash256::new(31, NonZeroU32::new(1000).unwrap());
+        assert_eq!(hasher.hash(b"ab"), ((97 * 31 + 98) * 31) % 1000);
+        assert_eq!(hasher.hash_steps("ab,\n"), vec![hasher.hash(b"ab"), 0]);
+    }
 }
@@ -15,7 +15,7 @@ pub struct Grid {
 }
 
 #[aoc_generator(day16)]
-fn parse(input: &str) -> Grid {
+pub(crate) fn parse(input: &str) -> Grid {
     Grid {
         field: input
             .lines()
@@ -109,14 +109,14 @@ fn dt(((x, y), (dx, dy)): Beam) -> Beam {
     ((x + dx, y + dy), (dx, dy))
 }
 #[aoc(day16, part1)]
-fn part1(input: &Grid) -> usize {
+pub(crate) fn part1(input: &Grid) -> usize {
     let field = input.field.clone();
     let tiles = energize(&field, (ORIGIN, EAST));
     tiles.len()
 }
 
 #[aoc(day16, part2)]
-fn part2(input: &Grid) -> usize {
+pub(crate) fn part2(input: &Grid) -> usize {
     max_energized(input)
 }
 
@@ -140,18 +140,8 @@ mod tests {
         assert_eq!(part1(&parse(EXAMPLE)), 46);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(include_str!("../input/2023/day16.txt"))), 7927);
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 51);
     }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(part2(&parse(include_str!("../input/2023/day16.txt"))), 8246);
-    }
 }
@@ -1,94 +1,209 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use glam::IVec2;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::{cmp::Reverse, collections::BinaryHeap};
 
-type Game = (HashMap<IVec2, u32>, IVec2);
+/// Heat loss per city block, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeatGrid {
+    width: i32,
+    height: i32,
+    cells: Vec<u32>,
+}
 
-#[aoc_generator(day17)]
-fn parse(input: &str) -> Game {
-    let y = input.lines().count() as i32;
-    let x = input.lines().next().unwrap().len() as i32;
-    let board = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.char_indices().map(move |(x, c)| {
-                (
-                    IVec2 {
-                        x: x as i32,
-                        y: y as i32,
-                    },
-                    c.to_digit(10).unwrap(),
-                )
-            })
-        })
-        .collect::<HashMap<IVec2, u32>>();
-    (board, IVec2 { x, y })
+impl HeatGrid {
+    pub fn size(&self) -> IVec2 {
+        IVec2 {
+            x: self.width,
+            y: self.height,
+        }
+    }
+
+    fn index(&self, pos: IVec2) -> Option<usize> {
+        if pos.x < 0 || pos.y < 0 || pos.x >= self.width || pos.y >= self.height {
+            None
+        } else {
+            Some((pos.y * self.width + pos.x) as usize)
+        }
+    }
+
+    fn position(&self, index: usize) -> IVec2 {
+        IVec2 {
+            x: index as i32 % self.width,
+            y: index as i32 / self.width,
+        }
+    }
 }
 
-fn find_path(game: &Game, part2: bool) -> u32 {
-    let start = IVec2::ZERO;
-    let pos_end = game.1 - IVec2::ONE;
-    let mut candidates: BTreeSet<(u32, [i32; 2], [i32; 2])> = BTreeSet::new();
-    let mut seen: HashSet<(IVec2, IVec2)> = HashSet::new();
-    candidates.insert((0, start.to_array(), [0, 0]));
-    while let Some((heat_loss, pos, dir)) = candidates.pop_first() {
-        let pos: IVec2 = pos.into();
-        let dir: IVec2 = dir.into();
-        if pos == pos_end {
-            return heat_loss;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CrucibleRules {
+    pub min_straight: u32,
+    pub max_straight: u32,
+    pub can_reverse: bool,
+}
+
+pub const CRUCIBLE: CrucibleRules = CrucibleRules {
+    min_straight: 1,
+    max_straight: 3,
+    can_reverse: false,
+};
+
+pub const ULTRA_CRUCIBLE: CrucibleRules = CrucibleRules {
+    min_straight: 4,
+    max_straight: 10,
+    can_reverse: false,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CruciblePath {
+    pub heat_loss: u32,
+    /// Every block visited, from start to end inclusive.
+    pub path: Vec<IVec2>,
+}
+
+const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y];
+/// Search states are a block plus the direction the crucible arrived in,
+/// with an extra slot for the start where it has not moved yet.
+const STATES_PER_BLOCK: usize = DIRECTIONS.len() + 1;
+const NOT_MOVED: usize = DIRECTIONS.len();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeatGridError {
+    Empty,
+    RaggedRow { row: usize, width: usize },
+    InvalidBlock(char),
+}
+
+impl std::fmt::Display for HeatGridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeatGridError::Empty => write!(f, "heat grid has no rows"),
+            HeatGridError::RaggedRow { row, width } => {
+                write!(f, "row {row} is not {width} blocks wide")
+            }
+            HeatGridError::InvalidBlock(c) => write!(f, "invalid heat loss {c:?}"),
+        }
+    }
+}
+
+impl HeatGrid {
+    pub fn parse(input: &str) -> Result<HeatGrid, HeatGridError> {
+        let lines = input
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let width = lines.first().ok_or(HeatGridError::Empty)?.len();
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(HeatGridError::RaggedRow { row, width });
+            }
+            for c in line.chars() {
+                cells.push(c.to_digit(10).ok_or(HeatGridError::InvalidBlock(c))?);
+            }
         }
-        if !seen.insert((pos, dir)) {
+        Ok(HeatGrid {
+            width: width as i32,
+            height: lines.len() as i32,
+            cells,
+        })
+    }
+}
+
+#[aoc_generator(day17)]
+pub(crate) fn parse(input: &str) -> HeatGrid {
+    HeatGrid::parse(input).unwrap_or_else(|e| panic!("Invalid heat grid: {e}"))
+}
+
+/// Directions the crucible may set off in next: never straight on, since a straight run is
+/// taken in full from the turn that started it, and only backwards if the rules allow.
+fn turns(dir: usize, rules: &CrucibleRules) -> impl Iterator<Item = usize> {
+    let can_reverse = rules.can_reverse;
+    (0..DIRECTIONS.len()).filter(move |&d| {
+        dir == NOT_MOVED || (d != dir && (can_reverse || d != (dir + 2) % DIRECTIONS.len()))
+    })
+}
+
+pub fn find_path(
+    grid: &HeatGrid,
+    rules: &CrucibleRules,
+    start: IVec2,
+    end: IVec2,
+) -> Option<CruciblePath> {
+    let start_idx = grid.index(start)?;
+    let end_idx = grid.index(end)?;
+    let state_count = grid.cells.len() * STATES_PER_BLOCK;
+    let mut heat_losses = vec![u32::MAX; state_count];
+    let mut previous = vec![usize::MAX; state_count];
+    let mut candidates = BinaryHeap::new();
+
+    let start_state = start_idx * STATES_PER_BLOCK + NOT_MOVED;
+    heat_losses[start_state] = 0;
+    candidates.push(Reverse((0, start_state)));
+    while let Some(Reverse((heat_loss, state))) = candidates.pop() {
+        if heat_loss > heat_losses[state] {
             continue;
         }
-        let possibles = match dir {
-            IVec2::X | IVec2::NEG_X => [IVec2::Y, IVec2::NEG_Y],
-            IVec2::Y | IVec2::NEG_Y => [IVec2::X, IVec2::NEG_X],
-            IVec2::ZERO => [IVec2::X, IVec2::Y],
-            _ => unreachable!("Invalid direction"),
-        };
-        for dir_new in possibles {
+        let (block, dir) = (state / STATES_PER_BLOCK, state % STATES_PER_BLOCK);
+        if block == end_idx {
+            return Some(CruciblePath {
+                heat_loss,
+                path: reconstruct(grid, &previous, state),
+            });
+        }
+        let pos = grid.position(block);
+        for dir_new in turns(dir, rules) {
             let mut hl = heat_loss;
             let mut new_pos = pos;
-            if part2 {
-                const MIN_STEP: i32 = 4;
-                const MAX_STEP: i32 = 10;
-                for step in 1..=MAX_STEP {
-                    new_pos += dir_new;
-                    if let Some(tile) = game.0.get(&new_pos) {
-                        hl += tile;
-                        if step >= MIN_STEP {
-                            candidates.insert((hl, new_pos.to_array(), dir_new.to_array()));
-                        }
-                    } else {
-                        break;
-                    }
-                }
-            } else {
-                for _ in 0..3 {
-                    new_pos += dir_new;
-                    if let Some(tile) = game.0.get(&new_pos) {
-                        hl += tile;
-                        candidates.insert((hl, new_pos.to_array(), dir_new.to_array()));
-                    } else {
-                        break;
-                    }
+            for step in 1..=rules.max_straight {
+                new_pos += DIRECTIONS[dir_new];
+                let Some(idx) = grid.index(new_pos) else {
+                    break;
+                };
+                hl += grid.cells[idx];
+                let next = idx * STATES_PER_BLOCK + dir_new;
+                if step >= rules.min_straight && hl < heat_losses[next] {
+                    heat_losses[next] = hl;
+                    previous[next] = state;
+                    candidates.push(Reverse((hl, next)));
                 }
             }
         }
     }
+    None
+}
+
+fn reconstruct(grid: &HeatGrid, previous: &[usize], end_state: usize) -> Vec<IVec2> {
+    let mut path = vec![grid.position(end_state / STATES_PER_BLOCK)];
+    let mut state = end_state;
+    while previous[state] != usize::MAX {
+        let from = grid.position(previous[state] / STATES_PER_BLOCK);
+        let step = DIRECTIONS[state % STATES_PER_BLOCK];
+        let mut pos = grid.position(state / STATES_PER_BLOCK);
+        while pos != from {
+            pos -= step;
+            path.push(pos);
+        }
+        state = previous[state];
+    }
+    path.reverse();
+    path
+}
 
-    unreachable!("No path found");
+fn corner_to_corner(grid: &HeatGrid, rules: &CrucibleRules) -> u32 {
+    find_path(grid, rules, IVec2::ZERO, grid.size() - IVec2::ONE)
+        .expect("No path found")
+        .heat_loss
 }
 
 #[aoc(day17, part1)]
-fn part1(input: &Game) -> u32 {
-    find_path(input, false)
+pub(crate) fn part1(input: &HeatGrid) -> u32 {
+    corner_to_corner(input, &CRUCIBLE)
 }
 
 #[aoc(day17, part2)]
-fn part2(input: &Game) -> u32 {
-    find_path(input, true)
+pub(crate) fn part2(input: &HeatGrid) -> u32 {
+    corner_to_corner(input, &ULTRA_CRUCIBLE)
 }
 
 #[cfg(test)]
@@ -115,17 +230,68 @@ mod tests {
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(include_str!("../input/2023/day17.txt"))), 967);
+    fn part2_example() {
+        assert_eq!(part2(&parse(EXAMPLE)), 94);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse(EXAMPLE)), 94);
+    fn path_accumulates_heat_loss() {
+        let grid = parse(EXAMPLE);
+        let end = grid.size() - IVec2::ONE;
+        let result = find_path(&grid, &ULTRA_CRUCIBLE, IVec2::ZERO, end).unwrap();
+        assert_eq!(result.path.first(), Some(&IVec2::ZERO));
+        assert_eq!(result.path.last(), Some(&end));
+        let walked: u32 = result.path[1..]
+            .iter()
+            .map(|p| grid.cells[grid.index(*p).unwrap()])
+            .sum();
+        assert_eq!(walked, result.heat_loss);
+        assert!(result.path.windows(2).all(|w| {
+            let step = (w[1] - w[0]).abs();
+            step.x + step.y == 1
+        }));
+    }
+
+    #[test]
+    fn custom_rules_and_endpoints() {
+        let grid = parse("19999\n11111");
+        let backtrack = CrucibleRules {
+            min_straight: 1,
+            max_straight: 4,
+            can_reverse: true,
+        };
+        let result = find_path(&grid, &backtrack, IVec2::new(0, 1), IVec2::new(4, 1)).unwrap();
+        assert_eq!(result.heat_loss, 4);
+        assert_eq!(result.path.len(), 5);
+        assert!(find_path(&grid, &CRUCIBLE, IVec2::ZERO, IVec2::new(9, 9)).is_none());
+    }
+
+    #[test]
+    fn reversing_escapes_a_corridor() {
+        let corridor = parse("11111");
+        let mut rules = CrucibleRules {
+            min_straight: 1,
+            max_straight: 2,
+            can_reverse: false,
+        };
+        let (start, end) = (IVec2::ZERO, IVec2::new(4, 0));
+        assert!(find_path(&corridor, &rules, start, end).is_none());
+        rules.can_reverse = true;
+        // Two forward, one back, and again until the end is reached
+        let result = find_path(&corridor, &rules, start, end).unwrap();
+        assert_eq!(result.heat_loss, 8);
     }
 
     #[test]
-    fn solves_part2() {
-        assert_eq!(part2(&parse(include_str!("../input/2023/day17.txt"))), 1101);
+    fn rejects_malformed_grids() {
+        assert_eq!(
+            HeatGrid::parse("123\n45\n678"),
+            Err(HeatGridError::RaggedRow { row: 1, width: 3 })
+        );
+        assert_eq!(
+            HeatGrid::parse("123\n4x6"),
+            Err(HeatGridError::InvalidBlock('x'))
+        );
+        assert_eq!(HeatGrid::parse("\n"), Err(HeatGridError::Empty));
     }
 }
@@ -1,75 +1,156 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use glam::IVec2;
+use nom::{
+    bytes::complete::{tag, take_while_m_n},
+    character::complete::{digit1, one_of, space0, space1},
+    combinator::{all_consuming, map, map_res},
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::point::Direction;
+use crate::polygon::{Polygon, Vertex};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
 
-pub type Move = (u8, i32);
+impl Rgb {
+    fn parse(input: &str) -> IResult<&str, Rgb> {
+        let hex_byte = || {
+            map_res(
+                take_while_m_n(2, 2, |ch: char| ch.is_ascii_hexdigit()),
+                |out: &str| u8::from_str_radix(out, 16),
+            )
+        };
+        map(
+            preceded(tag("#"), tuple((hex_byte(), hex_byte(), hex_byte()))),
+            |(r, g, b)| Rgb { r, g, b },
+        )(input)
+    }
 
-#[aoc_generator(day18, part1)]
-fn parse_part1(input: &str) -> Vec<Move> {
-    input.lines().map(|line| {
-        let instructions = line.split_ascii_whitespace().collect::<Vec<_>>();
-        (instructions[0].as_bytes()[0], instructions[1].parse::<i32>().unwrap())
-    }).collect()
+    fn value(&self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
 }
 
-#[aoc_generator(day18, part2)]
-fn parse_part2(input: &str) -> Vec<Move> {
-    input.lines().map(|line| {
-        let instructions = line.split_ascii_whitespace().collect::<Vec<_>>();
-        let hex = instructions[2];
-        let direction = match hex.as_bytes()[7] {
-            b'0' => b'R',
-            b'1' => b'D',
-            b'2' => b'L',
-            b'3' => b'U',
-            _ => unreachable!("Invalid direction"),
-        };
-        let hex_code = &hex[2..hex.len() - 2];
-        let amount = i32::from_str_radix(hex_code, 16).unwrap();
-        (direction, amount)
-    }).collect()
+/// How a dig instruction is read: part 1 uses the direction and length as written,
+/// part 2 decodes them from the color, five hex digits of length followed by one of direction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    Literal,
+    Hex,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DigInstruction {
+    pub dir: Direction,
+    pub len: i64,
+    pub color: Rgb,
+}
+
+impl DigInstruction {
+    fn parse(input: &str) -> IResult<&str, DigInstruction> {
+        let direction = map(one_of("UDLR"), |ch| match ch {
+            'U' => Direction::North,
+            'D' => Direction::South,
+            'L' => Direction::West,
+            _ => Direction::East,
+        });
+        let length = map_res(digit1, |out: &str| out.parse::<i64>());
+        map(
+            tuple((
+                preceded(space0, direction),
+                preceded(space1, length),
+                delimited(
+                    tuple((space1, tag("("))),
+                    Rgb::parse,
+                    terminated(tag(")"), space0),
+                ),
+            )),
+            |(dir, len, color)| DigInstruction { dir, len, color },
+        )(input)
+    }
+
+    /// The direction and length under `encoding`, or `None` if the color's last hex digit
+    /// is not a direction.
+    pub fn decode(&self, encoding: Encoding) -> Option<(Direction, i64)> {
+        match encoding {
+            Encoding::Literal => Some((self.dir, self.len)),
+            Encoding::Hex => {
+                let value = self.color.value();
+                let dir = match value & 0xF {
+                    0 => Direction::East,
+                    1 => Direction::South,
+                    2 => Direction::West,
+                    3 => Direction::North,
+                    _ => return None,
+                };
+                Some((dir, (value >> 4) as i64))
+            }
+        }
+    }
+}
+
+#[aoc_generator(day18)]
+pub(crate) fn parse(input: &str) -> Vec<DigInstruction> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match all_consuming(DigInstruction::parse)(line) {
+            Ok((_, instruction)) => instruction,
+            Err(e) => panic!("Invalid dig instruction {line:?}: {e}"),
+        })
+        .collect()
 }
 
 #[aoc(day18, part1)]
-fn part1(input: &[Move]) -> i64 {
-    lava(input)
+pub(crate) fn part1(input: &[DigInstruction]) -> i64 {
+    lava(input, Encoding::Literal)
 }
 
 #[aoc(day18, part2)]
-fn part2(input: &[Move]) -> i64 {
-    lava(input)
+pub(crate) fn part2(input: &[DigInstruction]) -> i64 {
+    lava(input, Encoding::Hex)
 }
 
-fn from_direction(direction: u8) -> IVec2 {
+fn offset(direction: Direction) -> Vertex {
     match direction {
-        b'U' => IVec2 { y: 1, x: 0 },
-        b'D' => IVec2 { y: -1, x: 0 },
-        b'L' => IVec2 { y: 0, x: -1 },
-        b'R' => IVec2 { y: 0, x: 1 },
-        _ => panic!("Invalid direction"),
+        Direction::North => (0, -1),
+        Direction::East => (1, 0),
+        Direction::South => (0, 1),
+        Direction::West => (-1, 0),
     }
 }
 
-fn lava(moves: &[Move]) -> i64 {
-    let mut previous;
-    let mut position = IVec2::ZERO;
-    let mut area: i64 = 0;
-    let mut perimeter: i64 = 0;
-
-    for &(direction, amount) in moves {
-        previous = position;
-        position += from_direction(direction) * amount;
-        area += determinant(position, previous);
-        perimeter += amount as i64;
+/// Corners of the trench dug by following the plan from the origin, in dig order, or `None`
+/// if an instruction does not decode.
+pub fn trench(plan: &[DigInstruction], encoding: Encoding) -> Option<Polygon> {
+    let mut position: Vertex = (0, 0);
+    let mut vertices = vec![position];
+    for instruction in plan {
+        let (direction, amount) = instruction.decode(encoding)?;
+        let (dx, dy) = offset(direction);
+        position = (position.0 + dx * amount, position.1 + dy * amount);
+        vertices.push(position);
+    }
+    if vertices.len() > 1 && vertices.last() == vertices.first() {
+        vertices.pop();
     }
-    (area / 2).abs() + (perimeter / 2+ 1) 
+    Some(Polygon::new(vertices))
 }
 
-fn determinant(a: IVec2, b: IVec2) -> i64 {
-    a.x as i64 * b.y as i64 - a.y as i64 * b.x as i64
-
+fn lava(plan: &[DigInstruction], encoding: Encoding) -> i64 {
+    let lagoon = trench(plan, encoding)
+        .unwrap_or_else(|| panic!("Dig plan cannot be decoded as {encoding:?}"));
+    let interior = lagoon
+        .interior_points()
+        .unwrap_or_else(|| panic!("Dig plan encloses no area"));
+    (interior + lagoon.boundary_points()) as i64
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,24 +170,67 @@ U 3 (#a77fa3)
 L 2 (#015232)
 U 2 (#7a21e3)"#;
 
-    const INPUT: &str = include_str!("../input/2023/day18.txt");
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse_part1(EXAMPLE)), 62);
+        assert_eq!(part1(&parse(EXAMPLE)), 62);
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse_part1(INPUT)), 33491);
+    fn part2_example() {
+        assert_eq!(part2(&parse(EXAMPLE)), 952408144115);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse_part2(EXAMPLE)), 952408144115);
+    fn parses_both_encodings() {
+        let plan = parse("  R 6   (#70c710) \n\nD 5 (#0dc571)\n");
+        assert_eq!(plan.len(), 2);
+        assert_eq!(
+            plan[0].color,
+            Rgb {
+                r: 0x70,
+                g: 0xc7,
+                b: 0x10
+            }
+        );
+        assert_eq!(
+            plan[0].decode(Encoding::Literal),
+            Some((Direction::East, 6))
+        );
+        assert_eq!(
+            plan[0].decode(Encoding::Hex),
+            Some((Direction::East, 461937))
+        );
+        assert_eq!(
+            plan[1].decode(Encoding::Hex),
+            Some((Direction::South, 56407))
+        );
     }
 
     #[test]
-    fn solves_part2() {
-        assert_eq!(part2(&parse_part2(INPUT)), 87716969654406);
+    fn bad_direction_digit_only_fails_hex() {
+        let plan = parse("R 6 (#70c714)\nL 6 (#70c712)");
+        assert_eq!(
+            plan[0].decode(Encoding::Literal),
+            Some((Direction::East, 6))
+        );
+        assert_eq!(plan[0].decode(Encoding::Hex), None);
+        assert!(trench(&plan, Encoding::Literal).is_some());
+        assert_eq!(trench(&plan, Encoding::Hex), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[should_panic(expected = "Invalid dig instruction")]
+    fn rejects_malformed_color() {
+        parse("R 6 (#70c7)");
+    }
+
+    #[test]
+    fn example_trench_is_closed() {
+        let lagoon = trench(&parse(EXAMPLE), Encoding::Literal).unwrap();
+        assert_eq!(lagoon.vertices.len(), 14);
+        assert_eq!(lagoon.vertices[0], (0, 0));
+        assert_eq!(lagoon.vertices[1], (6, 0));
+        assert_eq!(lagoon.vertices[2], (6, 5));
+        assert_eq!(lagoon.boundary_points(), 38);
+    }
+}
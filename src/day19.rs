@@ -402,7 +402,7 @@ impl Part {
 }
 
 #[aoc(day19, part1)]
-fn part1(input: &str) -> usize {
+pub(crate) fn part1(input: &str) -> usize {
     let (remaining, workflows) = WorkflowCollection::parse(input).unwrap();
     let mut input = remaining.trim();
     let mut score = 0;
@@ -415,7 +415,7 @@ fn part1(input: &str) -> usize {
 }
 
 #[aoc(day19, part2)]
-fn part2(input: &str) -> usize {
+pub(crate) fn part2(input: &str) -> usize {
     let (_, workflows) = WorkflowCollection::parse(input).unwrap();
     let starting_ranges = PartRange::default();
     let final_ranges = workflows.apply_ranges(starting_ranges);
@@ -447,24 +447,13 @@ hdj{m>838:A,pv}
 {x=2461,m=1339,a=466,s=291}
 {x=2127,m=1623,a=2188,s=1013}"#;
 
-    const INPUT: &str = include_str!("../input/2023/day19.txt");
     #[test]
     fn part1_example() {
         assert_eq!(part1(EXAMPLE), 19114);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(INPUT), 331208)
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 167409079868000);
     }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(part2(INPUT), 121464316215623);
-    }
 }
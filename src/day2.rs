@@ -1,88 +1,139 @@
-use std::cmp::max;
+use std::collections::BTreeMap;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    InvalidGame(String),
+    InvalidDraw(String),
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::InvalidGame(line) => write!(f, "invalid game {line:?}"),
+            GameError::InvalidDraw(draw) => write!(f, "invalid draw {draw:?}"),
+        }
+    }
+}
+
+/// How many cubes of each color, with colors not mentioned counting as zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CubeSet {
+    counts: BTreeMap<String, u32>,
+}
+
+impl CubeSet {
+    pub fn new<'a>(counts: impl IntoIterator<Item = (&'a str, u32)>) -> CubeSet {
+        CubeSet {
+            counts: counts
+                .into_iter()
+                .map(|(color, count)| (color.to_string(), count))
+                .collect(),
+        }
+    }
+
+    /// Parses a draw like `3 blue, 4 red`.
+    pub fn parse(draw: &str) -> Result<CubeSet, GameError> {
+        let invalid = || GameError::InvalidDraw(draw.trim().to_string());
+        let mut counts = BTreeMap::new();
+        for cubes in draw.split(',') {
+            let (count, color) = cubes.trim().split_once(' ').ok_or_else(invalid)?;
+            let count = count.parse::<u32>().map_err(|_| invalid())?;
+            let color = color.trim();
+            if color.is_empty() || color.contains(char::is_whitespace) {
+                return Err(invalid());
+            }
+            if counts.insert(color.to_string(), count).is_some() {
+                return Err(invalid());
+            }
+        }
+        Ok(CubeSet { counts })
+    }
+
+    pub fn get(&self, color: &str) -> u32 {
+        self.counts.get(color).copied().unwrap_or(0)
+    }
+
+    /// The smallest set containing both sets.
+    pub fn max(&self, other: &CubeSet) -> CubeSet {
+        let mut counts = self.counts.clone();
+        for (color, &count) in &other.counts {
+            let entry = counts.entry(color.clone()).or_default();
+            *entry = (*entry).max(count);
+        }
+        CubeSet { counts }
+    }
+
+    /// Whether every cube in `other` could have been drawn from this set.
+    pub fn contains(&self, other: &CubeSet) -> bool {
+        other
+            .counts
+            .iter()
+            .all(|(color, &count)| count <= self.get(color))
+    }
+
+    /// The product of the counts of `colors`, where a color missing from the set counts as 0.
+    pub fn power_over(&self, colors: &[&str]) -> u32 {
+        colors.iter().map(|color| self.get(color)).product()
+    }
+}
+
 #[derive(Debug)]
 pub struct Game {
-    pub rounds: Vec<GameRound>,
+    pub rounds: Vec<CubeSet>,
     pub id: usize,
 }
 
-#[derive(Debug)]
-pub struct GameRound {
-    pub red: Option<u32>,
-    pub blue: Option<u32>,
-    pub green: Option<u32>,
+impl Game {
+    fn parse(line: &str) -> Result<Game, GameError> {
+        let invalid = || GameError::InvalidGame(line.trim().to_string());
+        let (game, draws) = line.trim().split_once(':').ok_or_else(invalid)?;
+        let id = game
+            .strip_prefix("Game ")
+            .and_then(|id| id.trim().parse::<usize>().ok())
+            .ok_or_else(invalid)?;
+        let rounds = draws
+            .split(';')
+            .map(CubeSet::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Game { rounds, id })
+    }
+
+    /// The fewest cubes of each color the bag could have held.
+    pub fn minimum_bag(&self) -> CubeSet {
+        self.rounds
+            .iter()
+            .fold(CubeSet::default(), |bag, round| bag.max(round))
+    }
+}
+
+pub fn parse_games(input: &str) -> Result<Vec<Game>, GameError> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Game::parse)
+        .collect()
 }
 
 #[aoc_generator(day2)]
 pub fn generator(input: &str) -> Vec<Game> {
-    let mut games = Vec::new();
-    for line in input.lines() {
-        let mut game = Game {
-            rounds: Vec::new(),
-            id: 0,
-        };
-        let game_and_sets = line.trim().split(':').collect::<Vec<_>>();
-        let game_id = game_and_sets[0].split(' ').collect::<Vec<_>>()[1]
-            .parse::<usize>()
-            .unwrap();
-        game.id = game_id;
-        let game_sets: Vec<_> = game_and_sets[1].split(';').collect();
-        let game_sets = game_sets
-            .iter()
-            .map(|x| String::from(x.trim()))
-            .collect::<Vec<_>>();
-        for set in game_sets {
-            let mut game_round = GameRound {
-                red: None,
-                blue: None,
-                green: None,
-            };
-            let bags = set
-                .split(',')
-                .map(|x| x.trim().split(' ').collect::<Vec<_>>())
-                .collect::<Vec<_>>();
-            for bag in bags {
-                match bag[1] {
-                    "red" => {
-                        game_round.red = bag[0].parse::<u32>().ok();
-                    }
-                    "green" => {
-                        game_round.green = bag[0].parse::<u32>().ok();
-                    }
-                    "blue" => {
-                        game_round.blue = bag[0].parse::<u32>().ok();
-                    }
-                    _ => {}
-                }
-            }
-            game.rounds.push(game_round);
-        }
-        games.push(game);
-    }
-    games
+    parse_games(input).unwrap_or_else(|e| panic!("Invalid games: {e}"))
 }
 
-fn keep_game(game: &Game, max_red: u32, max_green: u32, max_blue: u32) -> bool {
-    let mut keep = true;
-    for round in &game.rounds {
-        if round.red.unwrap_or(0) > max_red
-            || round.green.unwrap_or(0) > max_green
-            || round.blue.unwrap_or(0) > max_blue
-        {
-            keep = false;
-            break;
-        }
-    }
-    keep
+fn keep_game(game: &Game, bag: &CubeSet) -> bool {
+    game.rounds.iter().all(|round| bag.contains(round))
 }
 
+/// The colors of cube in the elf's bag.
+const COLORS: [&str; 3] = ["red", "green", "blue"];
+
 #[aoc(day2, part1)]
 pub fn solve_part1(input: &[Game]) -> usize {
+    let bag = CubeSet::new(COLORS.into_iter().zip([12, 13, 14]));
     input
         .iter()
-        .filter(|g| keep_game(g, 12, 13, 14))
+        .filter(|g| keep_game(g, &bag))
         .map(|g| g.id)
         .sum()
 }
@@ -91,19 +142,7 @@ pub fn solve_part1(input: &[Game]) -> usize {
 pub fn solve_part2(input: &[Game]) -> u32 {
     input
         .iter()
-        .map(|g| {
-            let (min_red, min_green, min_blue) =
-                g.rounds
-                    .iter()
-                    .fold((0, 0, 0), |(red, green, blue), round| {
-                        (
-                            max(red, round.red.unwrap_or(0)),
-                            max(green, round.green.unwrap_or(0)),
-                            max(blue, round.blue.unwrap_or(0)),
-                        )
-                    });
-            min_red * min_green * min_blue
-        })
+        .map(|g| g.minimum_bag().power_over(&COLORS))
         .sum()
 }
 
@@ -121,7 +160,8 @@ mod tests {
         Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"#;
         let games = generator(input);
         assert_eq!(games.len(), 5);
-        assert_eq!(games.iter().filter(|g| keep_game(g, 12, 13, 14)).count(), 3);
+        let bag = CubeSet::new([("red", 12), ("green", 13), ("blue", 14)]);
+        assert_eq!(games.iter().filter(|g| keep_game(g, &bag)).count(), 3);
     }
 
     #[test]
@@ -134,4 +174,36 @@ mod tests {
         let games = generator(input);
         assert_eq!(solve_part2(&games), 2286)
     }
+
+    #[test]
+    pub fn any_colors() {
+        let games = generator("Game 7: 2 yellow, 1 red; 3 yellow\nGame 8: 1 purple");
+        let bag = CubeSet::new([("yellow", 3), ("red", 1)]);
+        assert!(keep_game(&games[0], &bag));
+        assert!(!keep_game(&games[1], &bag));
+        assert_eq!(games[0].minimum_bag(), bag);
+        assert_eq!(games[0].minimum_bag().power_over(&["yellow", "red"]), 3);
+        assert_eq!(games[1].minimum_bag().power_over(&["yellow", "red"]), 0);
+    }
+
+    #[test]
+    pub fn missing_color_has_no_power() {
+        assert_eq!(solve_part2(&generator("Game 1: 3 blue, 4 red")), 0);
+    }
+
+    #[test]
+    pub fn rejects_malformed_draws() {
+        assert_eq!(
+            parse_games("Game 1: 3 blue, red").unwrap_err(),
+            GameError::InvalidDraw("3 blue, red".to_string())
+        );
+        assert_eq!(
+            parse_games("Game 1: 3 blue; 2 red, 2 red").unwrap_err(),
+            GameError::InvalidDraw("2 red, 2 red".to_string())
+        );
+        assert_eq!(
+            parse_games("Round 1: 3 blue").unwrap_err(),
+            GameError::InvalidGame("Round 1: 3 blue".to_string())
+        );
+    }
 }
@@ -1,10 +1,10 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use aoc_runner_derive::{aoc};
+use aoc_runner_derive::aoc;
 use num::Integer;
 
 #[aoc(day20, part1)]
-fn part1(input: &str) -> usize {
+pub(crate) fn part1(input: &str) -> usize {
     let g = input
         .lines()
         .map(|l| {
@@ -73,7 +73,7 @@ fn part1(input: &str) -> usize {
 }
 
 #[aoc(day20, part2)]
-fn part2(input: &str) -> usize {
+pub(crate) fn part2(input: &str) -> usize {
     let g = input
         .lines()
         .map(|l| {
@@ -158,19 +158,8 @@ mod tests {
 %b -> con
 &con -> output"#;
 
-    const INPUT: &str = include_str!("../input/2023/day20.txt");
     #[test]
     fn part1_example() {
         assert_eq!(part1(EXAMPLE), 11687500);
     }
-
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(INPUT), 980457412);
-    }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(part2(INPUT), 232774988886497);
-    }
 }
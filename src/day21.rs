@@ -1,22 +1,16 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::VecDeque;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
-use crate::point::{Direction, Point};
+use crate::point::Point;
 
 pub type Grid = Vec<Vec<char>>;
 
 #[aoc_generator(day21)]
-fn parse(input: &str) -> (Grid, Point) {
+pub(crate) fn parse(input: &str) -> (Grid, Point) {
     let grid: Grid = input
         .lines()
-        .enumerate()
-        .map(move |(_y, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(_x, c)| c)
-                .collect::<Vec<_>>()
-        })
+        .map(|line| line.chars().collect::<Vec<_>>())
         .collect();
     let start = grid
         .iter()
@@ -37,154 +31,141 @@ fn parse(input: &str) -> (Grid, Point) {
     (grid, start)
 }
 
-fn passable(grid: &Grid, p: Point) -> bool {
-    grid[p.y as usize][p.x as usize] != '#'
+/// Shortest walking distance from the start to every plot of a block of `tiles`×`tiles`
+/// copies of the garden, with the start in the centre copy.
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    distances: Vec<u32>,
 }
 
-fn grid_contains(grid: &Grid, p: Point) -> bool {
-    p.x >= 0 && p.y >= 0 && p.x < grid[0].len() as isize && p.y < grid.len() as isize
-}
-
-fn visit(grid: &Grid, start: Point, max_steps: usize) -> usize {
-    let _max_x = grid[0].len();
-    let _max_y = grid.len();
-    let mut queue = VecDeque::new();
-    let mut visited = HashSet::<Point>::new();
-    visited.insert(start);
-    queue.push_back((start, 0));
-    let mut reached_count = 0;
-    let mut cur_count = 0;
-    while let Some((pos, steps)) = queue.pop_front() {
-        if steps == max_steps {
-            reached_count += 1;
-        }
+impl DistanceField {
+    const UNREACHABLE: u32 = u32::MAX;
 
-        if steps != cur_count {
-            cur_count = steps;
-            visited.clear();
-        }
+    pub fn single(grid: &Grid, start: Point) -> DistanceField {
+        DistanceField::tiled(grid, start, 1)
+    }
 
-        if steps > max_steps {
-            continue;
-        }
-        for d in enum_iterator::all::<Direction>() {
-            let neighbour = pos.neighbour(d);
-            if grid_contains(grid, neighbour)
-                && !visited.contains(&neighbour)
-                && passable(grid, neighbour)
-            {
-                visited.insert(neighbour);
-                queue.push_back((neighbour, steps + 1))
+    /// `tiles` is rounded up to the next odd number so the start tile stays in the centre.
+    pub fn tiled(grid: &Grid, start: Point, tiles: usize) -> DistanceField {
+        let tiles = tiles | 1;
+        let (tile_width, tile_height) = (grid[0].len(), grid.len());
+        let (width, height) = (tile_width * tiles, tile_height * tiles);
+        let passable = |x: usize, y: usize| grid[y % tile_height][x % tile_width] != '#';
+        let mut distances = vec![Self::UNREACHABLE; width * height];
+        let first = (start.y as usize + tiles / 2 * tile_height) * width
+            + start.x as usize
+            + tiles / 2 * tile_width;
+        distances[first] = 0;
+        let mut queue = VecDeque::from([first]);
+        while let Some(idx) = queue.pop_front() {
+            let (x, y) = (idx % width, idx / width);
+            let next_distance = distances[idx] + 1;
+            let neighbours = [
+                (y > 0).then(|| (x, y - 1)),
+                (x + 1 < width).then_some((x + 1, y)),
+                (y + 1 < height).then_some((x, y + 1)),
+                (x > 0).then(|| (x - 1, y)),
+            ];
+            for (nx, ny) in neighbours.into_iter().flatten() {
+                let n = ny * width + nx;
+                if distances[n] == Self::UNREACHABLE && passable(nx, ny) {
+                    distances[n] = next_distance;
+                    queue.push_back(n);
+                }
             }
         }
+        DistanceField { distances }
     }
 
-    reached_count
+    /// Plots where a walk of exactly `steps` can end. A walker can always step back and forth,
+    /// so that is every plot no further than `steps` away whose distance has the same parity.
+    pub fn reachable_in_exactly(&self, steps: usize) -> usize {
+        self.distances
+            .iter()
+            .filter(|&&d| d != Self::UNREACHABLE)
+            .filter(|&&d| d as usize <= steps && d as usize % 2 == steps % 2)
+            .count()
+    }
 }
 
-fn pos_mod<T>(a: T, b: T) -> T
-where
-    T: num::Integer + num::Signed + Copy,
-{
-    let c = a % b;
-    if c < num::zero() {
-        c + b
-    } else {
-        c
-    }
+fn visit(grid: &Grid, start: Point, max_steps: usize) -> usize {
+    DistanceField::single(grid, start).reachable_in_exactly(max_steps)
 }
-fn predict_perimeter(
-    cur_count: usize,
-    past_perims: &[usize],
-    past_perims_offsets: &[usize],
-) -> usize {
-    let term2 = past_perims_offsets[cur_count % 131];
-
-    ((cur_count / 131) - 1) * (past_perims[131] + term2) + past_perims[cur_count % 131 + 131]
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GardenError {
+    NotSquare { width: usize, height: usize },
+    NotQuadratic { stride: usize, offset: usize },
 }
-fn visit_infinite(grid: &Grid, start: Point, max_steps: usize) -> usize {
-    let mut queue = VecDeque::new();
-    let mut visited = HashSet::<Point>::new();
-    let mut reached_cache = HashMap::<usize, usize>::new();
-    let mut perim_cache = HashMap::<usize, usize>::new();
-    let mut past_perims = Vec::new();
-    let mut past_perims_offset = Vec::<usize>::new();
-    visited.insert(start);
-    queue.push_back((start, 0));
-    let mut gardens = 0;
-    let mut cur_count = 0;
-    while let Some((pos, count)) = queue.pop_front() {
-        if count != cur_count {
-            let mut interior_gardens = 0;
-            if cur_count >= 2 {
-                interior_gardens = *reached_cache.get(&(cur_count - 2)).unwrap();
-            }
-            let total_gardens = gardens + interior_gardens;
-            if cur_count < 262 {
-                past_perims.push(gardens);
-            } else if cur_count < 393 {
-                let predicted_perimeter =
-                    ((cur_count / 131) - 1) * past_perims[131] + past_perims[cur_count % 131 + 131];
-                let offset = gardens - predicted_perimeter;
-                past_perims_offset.push(offset);
-            } else {
-                let term2 = past_perims_offset[cur_count % 131];
-                let predicted_perim = ((cur_count / 131) - 1) * (past_perims[131] + term2)
-                    + past_perims[cur_count % 131 + 131];
-                let _offset = gardens as isize - predicted_perim as isize;
-                break;
-            }
-            reached_cache.insert(cur_count, total_gardens);
-            perim_cache.insert(cur_count, gardens);
-            gardens = 0;
-            cur_count = count;
-        }
-        gardens += 1;
-        if count >= max_steps {
-            continue;
-        }
-        for d in enum_iterator::all::<Direction>() {
-            let neighbour = pos.neighbour(d);
-            let mut neighbour_wrap = neighbour;
-            neighbour_wrap.x = pos_mod(neighbour.x, grid[0].len() as isize);
-            neighbour_wrap.y = pos_mod(neighbour.y, grid.len() as isize);
-            if grid_contains(grid, neighbour_wrap)
-                && !visited.contains(&neighbour)
-                && passable(grid, neighbour_wrap)
-            {
-                visited.insert(neighbour);
-                queue.push_back((neighbour, count + 1))
+
+impl std::fmt::Display for GardenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GardenError::NotSquare { width, height } => {
+                write!(
+                    f,
+                    "garden must be square to tile evenly, got {width}x{height}"
+                )
             }
+            GardenError::NotQuadratic { stride, offset } => write!(
+                f,
+                "reachable plots at {offset} + k*{stride} steps do not settle into a quadratic"
+            ),
         }
     }
-    let interior_gardens = *reached_cache.get(&(cur_count - 2)).unwrap();
-    let total_gardens = gardens + interior_gardens;
-    println!(
-        "After {cur_count} steps, perim {gardens} + inter {interior_gardens} = {total_gardens}"
-    );
-    let mut prev2 = *reached_cache.get(&(cur_count - 3)).unwrap();
-    let mut prev = *reached_cache.get(&(cur_count - 2)).unwrap()
-        + predict_perimeter(cur_count, &past_perims, &past_perims_offset);
-    loop {
-        let np2 = prev2 + predict_perimeter(cur_count + 1, &past_perims, &past_perims_offset);
-        let np = prev + predict_perimeter(cur_count + 2, &past_perims, &past_perims_offset);
-        prev2 = np2;
-        prev = np;
-        cur_count += 2;
-        if cur_count >= max_steps {
-            println!("{} - {prev2}, {} - {prev}", cur_count - 1, cur_count);
-            return prev;
-        }
+}
+
+/// How many sampling strides to explore before giving up on finding a quadratic.
+const MAX_SAMPLED_STRIDES: usize = 8;
+
+/// Counts plots reachable in exactly `steps` steps on the infinitely tiled garden.
+///
+/// The count sampled at `steps % stride + k * stride` grows quadratically in `k` once the
+/// walk has spread over a few tiles, so the samples are extrapolated after the second
+/// differences have been seen to agree twice in a row. The stride is the garden's period,
+/// doubled when the period is odd: the parity of reachable plots then flips from one tile to
+/// the next, and only an even stride samples each tile at the same parity every time.
+pub fn visit_infinite(grid: &Grid, start: Point, steps: usize) -> Result<usize, GardenError> {
+    let (width, height) = (grid[0].len(), grid.len());
+    if width != height {
+        return Err(GardenError::NotSquare { width, height });
     }
+    let period = width;
+    let stride = if period % 2 == 0 { period } else { 2 * period };
+    let offset = steps % stride;
+    let sampled_steps = offset + MAX_SAMPLED_STRIDES * stride;
+    // Covering `k` more periods needs `k` more tiles on each side of the start's tile
+    let tiles = 2 * (sampled_steps.min(steps) / period) + 3;
+    let field = DistanceField::tiled(grid, start, tiles);
+    if steps <= sampled_steps {
+        return Ok(field.reachable_in_exactly(steps));
+    }
+
+    let samples = (0..=MAX_SAMPLED_STRIDES)
+        .map(|k| field.reachable_in_exactly(offset + k * stride) as i128)
+        .collect::<Vec<_>>();
+    let second_differences = samples
+        .windows(3)
+        .map(|w| w[2] - 2 * w[1] + w[0])
+        .collect::<Vec<_>>();
+    let settled = second_differences
+        .windows(3)
+        .position(|w| w[0] == w[1] && w[1] == w[2])
+        .ok_or(GardenError::NotQuadratic { stride, offset })?;
+
+    let (base, second) = (settled as i128, second_differences[settled]);
+    let first = samples[settled + 1] - samples[settled];
+    let k = ((steps - offset) / stride) as i128 - base;
+    Ok((samples[settled] + k * first + k * (k - 1) / 2 * second) as usize)
 }
 
 #[aoc(day21, part1)]
-fn part1(input: &(Grid, Point)) -> usize {
+pub(crate) fn part1(input: &(Grid, Point)) -> usize {
     visit(&input.0, input.1, 64)
 }
 #[aoc(day21, part2)]
-fn part2(input: &(Grid, Point)) -> usize {
-    visit_infinite(&input.0, input.1, 26501365)
+pub(crate) fn part2(input: &(Grid, Point)) -> usize {
+    visit_infinite(&input.0, input.1, 26501365).unwrap()
 }
 
 #[cfg(test)]
@@ -211,7 +192,47 @@ mod tests {
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(INPUT)), 3594)
+    fn infinite_example() {
+        let (grid, start) = parse(EXAMPLE);
+        assert_eq!(visit_infinite(&grid, start, 10), Ok(50));
+        assert_eq!(visit_infinite(&grid, start, 50), Ok(1594));
+        assert_eq!(visit_infinite(&grid, start, 100), Ok(6536));
+        assert_eq!(visit_infinite(&grid, start, 500), Ok(167004));
+        assert_eq!(visit_infinite(&grid, start, 1000), Ok(668697));
+        assert_eq!(visit_infinite(&grid, start, 5000), Ok(16733044));
+    }
+
+    #[test]
+    fn off_centre_starts() {
+        let (grid, _) = parse(INPUT);
+        let corner = Point { x: 0, y: 0 };
+        assert_eq!(visit_infinite(&grid, corner, 1500), Ok(1939478));
+        assert_eq!(visit_infinite(&grid, corner, 2000), Ok(3449016));
+        // An odd period flips the parity of each tile, so these only settle at a double stride
+        let lopsided = Point { x: 10, y: 37 };
+        assert_eq!(visit_infinite(&grid, lopsided, 2000), Ok(3448024));
+        assert_eq!(visit_infinite(&grid, lopsided, 3000), Ok(7758138));
+    }
+
+    #[test]
+    fn rejects_non_square_gardens() {
+        let (wide, start) = parse("...\n.S.\n...\n...");
+        assert_eq!(
+            visit_infinite(&wide, start, 1000),
+            Err(GardenError::NotSquare {
+                width: 3,
+                height: 4
+            })
+        );
+    }
+
+    #[test]
+    fn one_field_answers_many_step_counts() {
+        let (grid, start) = parse(EXAMPLE);
+        let field = DistanceField::tiled(&grid, start, 11);
+        assert_eq!(field.reachable_in_exactly(0), 1);
+        for (steps, expected) in [(6, 16), (10, 50), (50, 1594)] {
+            assert_eq!(field.reachable_in_exactly(steps), expected);
+        }
     }
 }
@@ -1,13 +1,26 @@
-use std::collections::{HashMap, HashSet};
-
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
-pub type Brick = (usize, usize, usize, usize, usize, usize);
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Brick {
+    pub x1: usize,
+    pub y1: usize,
+    pub z1: usize,
+    pub x2: usize,
+    pub y2: usize,
+    pub z2: usize,
+}
+
+impl Brick {
+    fn footprint(&self) -> impl Iterator<Item = (usize, usize)> {
+        (self.x1..=self.x2).cartesian_product(self.y1..=self.y2)
+    }
+}
+
 pub type Bricks = Vec<Brick>;
 
 #[aoc_generator(day22)]
-fn parse(input: &str) -> Bricks {
+pub(crate) fn parse(input: &str) -> Bricks {
     let mut bricks: Bricks = input
         .lines()
         .map(|l| {
@@ -16,76 +29,187 @@ fn parse(input: &str) -> Bricks {
                 .map(|w| w.parse::<usize>().unwrap())
                 .collect_tuple()
                 .unwrap();
-            (x1, y1, z1, x2, y2, z2)
+            Brick {
+                x1: x1.min(x2),
+                y1: y1.min(y2),
+                z1: z1.min(z2),
+                x2: x1.max(x2),
+                y2: y1.max(y2),
+                z2: z1.max(z2),
+            }
         })
         .collect::<Vec<_>>();
-    bricks.sort_by_key(|b| b.2);
+    bricks.sort_by_key(|b| b.z1);
     bricks
 }
 
-fn disintegrate(
-    adjacent: &[(HashSet<usize>, HashSet<usize>)],
-    falling: &mut HashSet<usize>,
-    brick: usize,
-) {
-    falling.insert(brick);
-    for &above in &adjacent[brick].0 {
-        if adjacent[above].1.iter().all(|x| falling.contains(x)) {
-            disintegrate(adjacent, falling, above)
-        }
-    }
+/// Bricks after they have all fallen as far as they can, along with who rests on whom.
+///
+/// Bricks are reordered by their starting height, so a brick always comes after every brick
+/// that supports it. Every index taken or returned refers to that order, as seen in
+/// [`BrickStack::bricks`].
+#[derive(Debug, Clone)]
+pub struct BrickStack {
+    bricks: Bricks,
+    supports: Vec<Vec<usize>>,
+    supported_by: Vec<Vec<usize>>,
 }
 
-fn drop_all(mut bricks: Bricks, part2: bool) -> usize {
-    let mut adjacent = vec![(HashSet::new(), HashSet::new()); bricks.len()];
-    let mut space = HashMap::new();
-    for i in 0..bricks.len() {
-        let (x1, y1, mut z1, x2, y2, mut z2) = bricks[i];
-        while z1 > 1
-            && (x1..=x2)
-                .cartesian_product(y1..=y2)
-                .all(|(x, y)| !space.contains_key(&(x, y, z1 - 1)))
-        {
-            z2 -= 1;
-            z1 -= 1;
+impl BrickStack {
+    pub fn settle(bricks: &[Brick]) -> BrickStack {
+        let mut bricks = bricks.to_vec();
+        bricks.sort_by_key(|b| b.z1);
+        let width = bricks.iter().map(|b| b.x2 + 1).max().unwrap_or(0);
+        let depth = bricks.iter().map(|b| b.y2 + 1).max().unwrap_or(0);
+        // Height of the highest settled cube in each column and which brick it belongs to
+        let mut height_map: Vec<(usize, Option<usize>)> = vec![(0, None); width * depth];
+        let mut supports = vec![vec![]; bricks.len()];
+        let mut supported_by = vec![vec![]; bricks.len()];
+        for (i, brick) in bricks.iter_mut().enumerate() {
+            let floor = brick
+                .footprint()
+                .map(|(x, y)| height_map[y * width + x].0)
+                .max()
+                .unwrap_or(0);
+            let below = brick
+                .footprint()
+                .filter_map(|(x, y)| match height_map[y * width + x] {
+                    (z, Some(j)) if z == floor => Some(j),
+                    _ => None,
+                })
+                .unique()
+                .collect::<Vec<_>>();
+            for &j in &below {
+                supports[j].push(i);
+            }
+            supported_by[i] = below;
+            let fall = brick.z1 - (floor + 1);
+            brick.z1 -= fall;
+            brick.z2 -= fall;
+            for (x, y) in brick.footprint() {
+                height_map[y * width + x] = (brick.z2, Some(i));
+            }
+        }
+        BrickStack {
+            bricks,
+            supports,
+            supported_by,
         }
-        for (x, y) in (x1..=x2).cartesian_product(y1..=y2) {
-            for z in z1..=z2 {
-                space.insert((x, y, z), i);
+    }
+
+    /// The settled bricks, in the order the other methods index them.
+    pub fn bricks(&self) -> &[Brick] {
+        &self.bricks
+    }
+
+    /// Bricks resting directly on top of brick `i`.
+    pub fn supports(&self, i: usize) -> &[usize] {
+        &self.supports[i]
+    }
+
+    /// Bricks that brick `i` rests directly on, empty if it is on the ground.
+    pub fn supported_by(&self, i: usize) -> &[usize] {
+        &self.supported_by[i]
+    }
+
+    /// Bricks that can be disintegrated without any other brick falling.
+    pub fn safe_to_remove(&self) -> Vec<usize> {
+        (0..self.bricks.len())
+            .filter(|&i| {
+                self.supports(i)
+                    .iter()
+                    .all(|&above| self.supported_by(above).len() > 1)
+            })
+            .collect()
+    }
+
+    /// How many other bricks fall if brick `i` is disintegrated.
+    pub fn chain_reaction(&self, i: usize) -> usize {
+        let mut falling = vec![false; self.bricks.len()];
+        falling[i] = true;
+        let mut count = 0;
+        for j in i + 1..self.bricks.len() {
+            let below = self.supported_by(j);
+            if !below.is_empty() && below.iter().all(|&k| falling[k]) {
+                falling[j] = true;
+                count += 1;
             }
-            if let Some(&j) = space.get(&(x, y, z1 - 1)) {
-                adjacent[j].0.insert(i);
-                adjacent[i].1.insert(j);
+        }
+        count
+    }
+
+    /// Immediate dominator of every brick in the support graph, where the ground is
+    /// `self.bricks.len()`. A brick falls when any of its dominators is disintegrated.
+    fn immediate_dominators(&self) -> Vec<usize> {
+        let ground = self.bricks.len();
+        let levels = (usize::BITS - ground.leading_zeros()) as usize + 1;
+        let mut depth = vec![0; ground + 1];
+        // ancestors[k][i] is the dominator 2^k steps above brick i
+        let mut ancestors = vec![vec![ground; ground + 1]; levels];
+        let lowest_common =
+            |mut a: usize, mut b: usize, depth: &[usize], ancestors: &[Vec<usize>]| {
+                if depth[a] < depth[b] {
+                    std::mem::swap(&mut a, &mut b);
+                }
+                for k in (0..levels).rev() {
+                    if depth[a] - depth[b] >= 1 << k {
+                        a = ancestors[k][a];
+                    }
+                }
+                if a == b {
+                    return a;
+                }
+                for k in (0..levels).rev() {
+                    if ancestors[k][a] != ancestors[k][b] {
+                        a = ancestors[k][a];
+                        b = ancestors[k][b];
+                    }
+                }
+                ancestors[0][a]
+            };
+        // Supporters always come earlier, so their dominators are known by the time we get here
+        for i in 0..ground {
+            let idom = self
+                .supported_by(i)
+                .iter()
+                .copied()
+                .reduce(|a, b| lowest_common(a, b, &depth, &ancestors))
+                .unwrap_or(ground);
+            depth[i] = depth[idom] + 1;
+            ancestors[0][i] = idom;
+            for k in 1..levels {
+                ancestors[k][i] = ancestors[k - 1][ancestors[k - 1][i]];
             }
         }
-        bricks[i] = (x1, y1, z1, x2, y2, z2);
+        ancestors.swap_remove(0)
     }
-    let mut falling = HashSet::new();
-    let mut ans = 0;
-    for b in 0..bricks.len() {
-        falling.clear();
-        disintegrate(&adjacent, &mut falling, b);
-        if part2 {
-            ans += falling.len() - 1
-        } else {
-            ans += (falling.len() == 1) as usize;
+
+    /// `chain_reaction` for every brick at once, from the sizes of the dominator subtrees.
+    pub fn falling_counts(&self) -> Vec<usize> {
+        let idom = self.immediate_dominators();
+        let mut counts = vec![0; self.bricks.len() + 1];
+        for i in (0..self.bricks.len()).rev() {
+            counts[idom[i]] += counts[i] + 1;
         }
+        counts.truncate(self.bricks.len());
+        counts
     }
-    ans
 }
+
 #[aoc(day22, part1)]
-fn part1(input: &Bricks) -> usize {
-    drop_all(input.clone(), false)
+pub(crate) fn part1(input: &Bricks) -> usize {
+    BrickStack::settle(input).safe_to_remove().len()
 }
 
 #[aoc(day22, part2)]
-fn part2(input: &Bricks) -> usize {
-    drop_all(input.clone(), true)
+pub(crate) fn part2(input: &Bricks) -> usize {
+    BrickStack::settle(input).falling_counts().iter().sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::{HashMap, HashSet};
     const EXAMPLE: &str = r#"1,0,1~1,2,1
 0,0,2~2,0,2
 0,2,3~2,2,3
@@ -106,12 +230,99 @@ mod tests {
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(INPUT)), 522);
+    fn example_support_graph() {
+        let stack = BrickStack::settle(&parse(EXAMPLE));
+        assert_eq!(stack.supports(0), &[1, 2]);
+        assert_eq!(stack.supported_by(3), &[1, 2]);
+        assert_eq!(stack.supported_by(0), &[] as &[usize]);
+        assert_eq!(stack.safe_to_remove(), vec![1, 2, 3, 4, 6]);
+        assert_eq!(stack.chain_reaction(0), 6);
+        assert_eq!(stack.chain_reaction(5), 1);
+        assert_eq!((stack.bricks[6].z1, stack.bricks[6].z2), (5, 6));
+    }
+
+    #[test]
+    fn indices_follow_height_order() {
+        let high = Brick {
+            x1: 0,
+            y1: 0,
+            z1: 5,
+            x2: 0,
+            y2: 0,
+            z2: 5,
+        };
+        let low = Brick {
+            z1: 1,
+            z2: 1,
+            ..high
+        };
+        let stack = BrickStack::settle(&[high, low]);
+        assert_eq!(stack.supports(0), &[1]);
+        assert_eq!(stack.bricks()[0], low);
+        assert_eq!(
+            stack.bricks()[1],
+            Brick {
+                z1: 2,
+                z2: 2,
+                ..high
+            }
+        );
+    }
+
+    /// The original approach: settle cube by cube, then recursively knock out each brick.
+    fn reference_falling_counts(bricks: &[Brick]) -> Vec<usize> {
+        fn disintegrate(
+            adjacent: &[(HashSet<usize>, HashSet<usize>)],
+            falling: &mut HashSet<usize>,
+            brick: usize,
+        ) {
+            falling.insert(brick);
+            for &above in &adjacent[brick].0 {
+                if adjacent[above].1.iter().all(|x| falling.contains(x)) {
+                    disintegrate(adjacent, falling, above)
+                }
+            }
+        }
+
+        let mut adjacent = vec![(HashSet::new(), HashSet::new()); bricks.len()];
+        let mut space = HashMap::new();
+        for (i, brick) in bricks.iter().enumerate() {
+            let (mut z1, mut z2) = (brick.z1, brick.z2);
+            while z1 > 1
+                && brick
+                    .footprint()
+                    .all(|(x, y)| !space.contains_key(&(x, y, z1 - 1)))
+            {
+                z2 -= 1;
+                z1 -= 1;
+            }
+            for (x, y) in brick.footprint() {
+                for z in z1..=z2 {
+                    space.insert((x, y, z), i);
+                }
+                if let Some(&j) = space.get(&(x, y, z1 - 1)) {
+                    adjacent[j].0.insert(i);
+                    adjacent[i].1.insert(j);
+                }
+            }
+        }
+        (0..bricks.len())
+            .map(|b| {
+                let mut falling = HashSet::new();
+                disintegrate(&adjacent, &mut falling, b);
+                falling.len() - 1
+            })
+            .collect()
     }
 
     #[test]
-    fn solves_part2() {
-        assert_eq!(part2(&parse(INPUT)), 83519);
+    fn dominators_agree_with_recursive_disintegration() {
+        let bricks = parse(INPUT);
+        let stack = BrickStack::settle(&bricks);
+        assert_eq!(stack.falling_counts(), reference_falling_counts(&bricks));
+        let per_brick = (0..stack.bricks.len())
+            .map(|i| stack.chain_reaction(i))
+            .collect::<Vec<_>>();
+        assert_eq!(stack.falling_counts(), per_brick);
     }
 }
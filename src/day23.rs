@@ -1,128 +1,231 @@
 use std::collections::{HashMap, HashSet};
 
 use aoc_runner_derive::{aoc, aoc_generator};
+use rayon::prelude::*;
 
 use crate::point::{Direction, Point};
 
 pub type HikingMap = Vec<Vec<Tile>>;
-#[derive(Clone, Debug)]
-enum Tile {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Tile {
     Path,
     Forest,
     Slope(Direction),
 }
 
-#[aoc_generator(day23)]
-fn parse(input: &str) -> HikingMap {
+impl Tile {
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Path => '.',
+            Tile::Forest => '#',
+            Tile::Slope(Direction::North) => '^',
+            Tile::Slope(Direction::South) => 'v',
+            Tile::Slope(Direction::West) => '<',
+            Tile::Slope(Direction::East) => '>',
+        }
+    }
+
+    /// Whether a hiker can step onto this tile while heading in `dir`.
+    fn enterable(&self, dir: Direction, ignore_slopes: bool) -> bool {
+        match self {
+            Tile::Path => true,
+            Tile::Forest => false,
+            Tile::Slope(downhill) => ignore_slopes || *downhill == dir,
+        }
+    }
+}
+
+impl TryFrom<char> for Tile {
+    type Error = char;
+
+    fn try_from(tile: char) -> Result<Self, Self::Error> {
+        match tile {
+            '.' => Ok(Tile::Path),
+            '#' => Ok(Tile::Forest),
+            '^' => Ok(Tile::Slope(Direction::North)),
+            'v' => Ok(Tile::Slope(Direction::South)),
+            '<' => Ok(Tile::Slope(Direction::West)),
+            '>' => Ok(Tile::Slope(Direction::East)),
+            _ => Err(tile),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrailError {
+    UnknownTile {
+        tile: char,
+        at: Point,
+    },
+    /// Auto-detection needs exactly two openings in the border of the map.
+    AmbiguousEndpoints(Vec<Point>),
+    NotOnTrail(Point),
+}
+
+impl std::fmt::Display for TrailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrailError::UnknownTile { tile, at } => write!(f, "unknown tile {tile:?} at {at}"),
+            TrailError::AmbiguousEndpoints(openings) => write!(
+                f,
+                "expected exactly two openings in the border, found {}",
+                openings.len()
+            ),
+            TrailError::NotOnTrail(point) => write!(f, "{point} is not on a trail"),
+        }
+    }
+}
+
+fn parse_map(input: &str) -> Result<HikingMap, TrailError> {
     input
         .lines()
-        .map(|line| {
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(y, line)| {
             line.chars()
-                .map(|tile| match tile {
-                    '.' => Tile::Path,
-                    '#' => Tile::Forest,
-                    '^' => Tile::Slope(Direction::North),
-                    'v' => Tile::Slope(Direction::South),
-                    '<' => Tile::Slope(Direction::West),
-                    '>' => Tile::Slope(Direction::East),
-                    _ => unreachable!("Invalid tile: {}", tile),
+                .enumerate()
+                .map(|(x, tile)| {
+                    Tile::try_from(tile).map_err(|tile| TrailError::UnknownTile {
+                        tile,
+                        at: Point {
+                            x: x as isize,
+                            y: y as isize,
+                        },
+                    })
                 })
                 .collect()
         })
         .collect()
 }
-fn starting_point(hiking_map: &[Vec<Tile>]) -> Option<Point> {
-    for (x, point) in hiking_map[0].iter().enumerate() {
-        match point {
-            Tile::Path => {
-                return Some(Point {
-                    x: x as isize,
-                    y: 0,
-                })
-            }
-            _ => continue,
-        }
+
+#[aoc_generator(day23)]
+pub(crate) fn parse(input: &str) -> HikingMap {
+    parse_map(input).unwrap_or_else(|e| panic!("Invalid hiking map: {e}"))
+}
+
+fn tile_at(hiking_map: &[Vec<Tile>], point: Point) -> Option<&Tile> {
+    if point.x < 0 || point.y < 0 {
+        return None;
     }
-    None
+    hiking_map.get(point.y as usize)?.get(point.x as usize)
 }
 
-fn finishing_point(hiking_map: &[Vec<Tile>]) -> Option<Point> {
-    for (x, point) in hiking_map[hiking_map.len() - 1].iter().enumerate() {
-        match point {
-            Tile::Path => {
-                return Some(Point {
-                    x: x as isize,
-                    y: (hiking_map.len() - 1) as isize,
-                })
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Endpoints {
+    pub fn new(
+        hiking_map: &[Vec<Tile>],
+        start: Point,
+        end: Point,
+    ) -> Result<Endpoints, TrailError> {
+        for point in [start, end] {
+            match tile_at(hiking_map, point) {
+                Some(Tile::Forest) | None => return Err(TrailError::NotOnTrail(point)),
+                _ => {}
             }
-            _ => continue,
         }
+        Ok(Endpoints { start, end })
     }
-    None
-}
-fn bottom_right(hiking_map: &[Vec<Tile>]) -> Point {
-    Point {
-        x: hiking_map[0].len() as isize - 1,
-        y: hiking_map.len() as isize - 1,
+
+    /// Finds the two trail tiles in the border of the map, starting from the one
+    /// that comes first in reading order.
+    pub fn detect(hiking_map: &[Vec<Tile>]) -> Result<Endpoints, TrailError> {
+        let (height, width) = (hiking_map.len(), hiking_map.first().map_or(0, |r| r.len()));
+        let openings = hiking_map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, tile)| (x, y, tile)))
+            .filter(|&(x, y, tile)| {
+                (x == 0 || y == 0 || x + 1 == width || y + 1 == height) && *tile != Tile::Forest
+            })
+            .map(|(x, y, _)| Point {
+                x: x as isize,
+                y: y as isize,
+            })
+            .collect::<Vec<_>>();
+        match openings[..] {
+            [start, end] => Ok(Endpoints { start, end }),
+            _ => Err(TrailError::AmbiguousEndpoints(openings)),
+        }
     }
 }
 
 fn adjacent(point: Point, hiking_map: &[Vec<Tile>], ignore_slopes: bool) -> Vec<Point> {
-    let bottom_right = bottom_right(hiking_map);
     enum_iterator::all::<Direction>()
-        .filter_map(|dir| {
-            let next = point.neighbour(dir);
-            if next.x >= 0 && next.x <= bottom_right.x && next.y >= 0 && next.y <= bottom_right.y {
-                match hiking_map[next.y as usize][next.x as usize] {
-                    Tile::Path => Some(next),
-                    Tile::Slope(d) => {
-                        if ignore_slopes || d == dir {
-                            Some(next)
-                        } else {
-                            None
-                        }
-                    }
-                    _ => None,
-                }
-            } else {
-                None
-            }
+        .map(|dir| (dir, point.neighbour(dir)))
+        .filter(|&(dir, next)| {
+            tile_at(hiking_map, next).is_some_and(|tile| tile.enterable(dir, ignore_slopes))
         })
+        .map(|(_, next)| next)
         .collect::<Vec<_>>()
 }
 
+/// Follows the corridor leaving `point` through `first` until it reaches a junction or dead end,
+/// returning every tile walked.
+fn walk_trail(
+    point: Point,
+    first: Point,
+    hiking_map: &[Vec<Tile>],
+    ignore_slopes: bool,
+) -> Vec<Point> {
+    let mut visited = HashSet::from([point]);
+    let mut trail = vec![];
+    let mut nodes = vec![first];
+    while let Some(pos) = nodes.pop() {
+        visited.insert(pos);
+        trail.push(pos);
+        let neighbours = adjacent(pos, hiking_map, ignore_slopes)
+            .into_iter()
+            .filter(|new_pos| !visited.contains(new_pos))
+            .collect::<Vec<_>>();
+        if neighbours.len() == 1 {
+            nodes.push(*neighbours.first().unwrap());
+        }
+    }
+    trail
+}
+
 fn get_connected_points(
     point: Point,
+    end: Point,
     hiking_map: &[Vec<Tile>],
     ignore_slopes: bool,
 ) -> HashMap<Point, usize> {
     let mut connected_points = HashMap::new();
-    if point == finishing_point(hiking_map).unwrap() {
+    if point == end {
         return connected_points;
-    } else {
-        for starting in adjacent(point, hiking_map, ignore_slopes) {
-            let mut visited = HashSet::from([point]);
-            let mut nodes = vec![(starting, 1)];
-            while let Some((pos, dist)) = nodes.pop() {
-                visited.insert(pos);
-                let neighbours = adjacent(pos, hiking_map, ignore_slopes)
-                    .into_iter()
-                    .filter(|new_pos| !visited.contains(new_pos))
-                    .collect::<Vec<_>>();
-                if neighbours.len() == 1 {
-                    nodes.push((*neighbours.first().unwrap(), dist + 1));
-                } else {
-                    let prev_dist = connected_points.entry(pos).or_default();
-                    *prev_dist = usize::max(*prev_dist, dist);
-                }
-            }
-        }
+    }
+    for starting in adjacent(point, hiking_map, ignore_slopes) {
+        let trail = walk_trail(point, starting, hiking_map, ignore_slopes);
+        let prev_dist = connected_points.entry(*trail.last().unwrap()).or_default();
+        *prev_dist = usize::max(*prev_dist, trail.len());
     }
     connected_points
 }
 
+/// The longest corridor between two neighbouring junctions, excluding `from`.
+fn trail_between(
+    from: Point,
+    to: Point,
+    hiking_map: &[Vec<Tile>],
+    ignore_slopes: bool,
+) -> Vec<Point> {
+    adjacent(from, hiking_map, ignore_slopes)
+        .into_iter()
+        .map(|starting| walk_trail(from, starting, hiking_map, ignore_slopes))
+        .filter(|trail| trail.last() == Some(&to))
+        .max_by_key(|trail| trail.len())
+        .unwrap_or_else(|| panic!("No trail from {from} to {to}"))
+}
+
 fn topological_sort(
     hiking_map: &[Vec<Tile>],
+    end: Point,
     current_point: Point,
     sorted: &mut Vec<Point>,
     edges: &mut HashMap<Point, HashMap<Point, usize>>,
@@ -130,11 +233,11 @@ fn topological_sort(
 ) {
     let connected = edges
         .entry(current_point)
-        .or_insert(get_connected_points(current_point, hiking_map, false))
+        .or_insert(get_connected_points(current_point, end, hiking_map, false))
         .clone();
     for (point, _) in connected {
         if !visited.contains(&point) {
-            topological_sort(hiking_map, point, sorted, edges, visited);
+            topological_sort(hiking_map, end, point, sorted, edges, visited);
         }
     }
     visited.insert(current_point);
@@ -143,13 +246,13 @@ fn topological_sort(
 
 fn calculate_edges(
     hiking_map: &[Vec<Tile>],
+    endpoints: Endpoints,
     ignore_slopes: bool,
 ) -> HashMap<Point, HashMap<Point, usize>> {
-    let start = starting_point(hiking_map).unwrap();
-    let mut vertices = vec![start];
+    let mut vertices = vec![endpoints.start];
     let mut edges = HashMap::new();
     while let Some(p) = vertices.pop() {
-        let connected = get_connected_points(p, hiking_map, ignore_slopes);
+        let connected = get_connected_points(p, endpoints.end, hiking_map, ignore_slopes);
         vertices.extend(
             connected
                 .keys()
@@ -160,15 +263,17 @@ fn calculate_edges(
     edges
 }
 
-fn longest_path_with_slopes(hiking_map: &[Vec<Tile>]) -> usize {
-    let start = starting_point(hiking_map).unwrap();
+fn longest_path_with_slopes(hiking_map: &[Vec<Tile>], endpoints: Endpoints) -> Hike {
+    let Endpoints { start, end } = endpoints;
     let mut distances_from_start = HashMap::from([(start, 0)]);
+    let mut previous = HashMap::new();
     let mut sorted_points = Vec::new();
     let mut edges = HashMap::new();
     let mut visited = HashSet::from([start]);
 
     topological_sort(
         hiking_map,
+        end,
         start,
         &mut sorted_points,
         &mut edges,
@@ -178,70 +283,221 @@ fn longest_path_with_slopes(hiking_map: &[Vec<Tile>]) -> usize {
         let distance_to_point = *distances_from_start.entry(point).or_default();
         for (connected, dist) in edges.get(&point).unwrap() {
             let prev_dist = distances_from_start.entry(*connected).or_default();
-            *prev_dist = usize::max(*prev_dist, distance_to_point + *dist);
+            if distance_to_point + *dist > *prev_dist {
+                *prev_dist = distance_to_point + *dist;
+                previous.insert(*connected, point);
+            }
         }
     }
-    *distances_from_start
-        .get(&finishing_point(hiking_map).unwrap())
-        .unwrap()
+    let mut junctions = vec![end];
+    while let Some(point) = previous.get(junctions.last().unwrap()) {
+        junctions.push(*point);
+    }
+    junctions.reverse();
+    Hike::along(hiking_map, junctions, false)
 }
 
-fn check_all_paths(
-    current_point: Point,
-    finishing_point: Point,
-    path: &mut Vec<Point>,
-    path_len: usize,
-    edges: &HashMap<Point, HashMap<Point, usize>>,
-    max_path_len: &mut usize,
-) {
-    if path.contains(&current_point) {
-        return;
+/// The trail network reduced to its junctions, numbered so visited sets fit in a `u64`.
+struct JunctionGraph {
+    junctions: Vec<Point>,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl JunctionGraph {
+    fn new(edges: &HashMap<Point, HashMap<Point, usize>>) -> JunctionGraph {
+        let mut junctions = edges
+            .iter()
+            .flat_map(|(from, to)| std::iter::once(*from).chain(to.keys().copied()))
+            .collect::<Vec<_>>();
+        junctions.sort_by_key(|p| (p.y, p.x));
+        junctions.dedup();
+        assert!(
+            junctions.len() <= u64::BITS as usize,
+            "Too many junctions for a u64 visited set: {}",
+            junctions.len()
+        );
+        let ids = junctions
+            .iter()
+            .enumerate()
+            .map(|(id, p)| (*p, id))
+            .collect::<HashMap<_, _>>();
+        let mut graph_edges = vec![vec![]; junctions.len()];
+        for (from, to) in edges {
+            graph_edges[ids[from]] = to.iter().map(|(p, dist)| (ids[p], *dist)).collect();
+            graph_edges[ids[from]].sort();
+        }
+        JunctionGraph {
+            junctions,
+            edges: graph_edges,
+        }
     }
 
-    path.push(current_point);
+    fn id(&self, point: Point) -> usize {
+        self.junctions.iter().position(|p| *p == point).unwrap()
+    }
+}
 
-    if current_point == finishing_point {
-        *max_path_len = usize::max(*max_path_len, path_len);
-        path.pop();
-        return;
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hike {
+    pub length: usize,
+    pub junctions: Vec<Point>,
+    /// Every tile stepped on, from the start to the end.
+    pub tiles: Vec<Point>,
+}
+
+impl Hike {
+    fn along(hiking_map: &[Vec<Tile>], junctions: Vec<Point>, ignore_slopes: bool) -> Hike {
+        let mut tiles = junctions[..1].to_vec();
+        for pair in junctions.windows(2) {
+            tiles.extend(trail_between(pair[0], pair[1], hiking_map, ignore_slopes));
+        }
+        Hike {
+            length: tiles.len() - 1,
+            junctions,
+            tiles,
+        }
     }
 
-    for (connected_vertex, dist) in edges.get(&current_point).unwrap().clone() {
+    /// Draws the map with the hike marked in `O`s, starting from an `S`.
+    pub fn render(&self, hiking_map: &[Vec<Tile>]) -> String {
+        let mut canvas = hiking_map
+            .iter()
+            .map(|row| row.iter().map(Tile::symbol).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for (i, tile) in self.tiles.iter().enumerate() {
+            canvas[tile.y as usize][tile.x as usize] = if i == 0 { 'S' } else { 'O' };
+        }
+        canvas
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A partial hike: visited set, length so far and the junctions walked.
+type Branch = (u64, usize, Vec<usize>);
+
+/// Keep splitting the search until there are at least this many branches to hand out to threads.
+const PARALLEL_BRANCHES: usize = 64;
+
+fn check_all_paths(
+    graph: &JunctionGraph,
+    target: usize,
+    visited: u64,
+    path_len: usize,
+    path: &mut Vec<usize>,
+    best: &mut (usize, Vec<usize>),
+) {
+    let current = *path.last().unwrap();
+    if current == target {
+        if path_len > best.0 || best.1.is_empty() {
+            *best = (path_len, path.clone());
+        }
+        return;
+    }
+    for &(next, dist) in &graph.edges[current] {
+        if visited & (1 << next) != 0 {
+            continue;
+        }
+        path.push(next);
         check_all_paths(
-            connected_vertex,
-            finishing_point,
-            path,
+            graph,
+            target,
+            visited | 1 << next,
             path_len + dist,
-            edges,
-            max_path_len,
+            path,
+            best,
         );
+        path.pop();
     }
-    path.pop();
 }
 
-fn longest_path_without_slopes(hiking_map: &[Vec<Tile>]) -> usize {
-    let start = starting_point(hiking_map).unwrap();
-    let end = finishing_point(hiking_map).unwrap();
-    let edges = calculate_edges(hiking_map, true);
-    let mut path = Vec::new();
-    let mut max_path_len = 0;
-    check_all_paths(start, end, &mut path, 0, &edges, &mut max_path_len);
-    max_path_len
+fn longest_path_without_slopes(hiking_map: &[Vec<Tile>], endpoints: Endpoints) -> Hike {
+    let edges = calculate_edges(hiking_map, endpoints, true);
+    let graph = JunctionGraph::new(&edges);
+    let start = graph.id(endpoints.start);
+    let end = graph.id(endpoints.end);
+
+    // Once at the only junction leading to the end, any detour would cut us off from it,
+    // so we can stop there and add the final stretch afterwards.
+    let into_end = (0..graph.junctions.len())
+        .flat_map(|from| {
+            graph.edges[from]
+                .iter()
+                .map(move |&(to, dist)| (from, to, dist))
+        })
+        .filter(|&(_, to, _)| to == end)
+        .collect::<Vec<_>>();
+    let (target, final_stretch) = match into_end[..] {
+        [(from, _, dist)] if from != start => (from, dist),
+        _ => (end, 0),
+    };
+
+    let mut branches: Vec<Branch> = vec![(1 << start, 0, vec![start])];
+    while branches.len() < PARALLEL_BRANCHES {
+        let (done, open): (Vec<_>, Vec<_>) = branches
+            .iter()
+            .flat_map(|(visited, path_len, path)| {
+                let current = *path.last().unwrap();
+                let children = graph.edges[current]
+                    .iter()
+                    .filter(move |(next, _)| current != target && visited & (1 << next) == 0)
+                    .map(move |&(next, dist)| {
+                        let mut path = path.clone();
+                        path.push(next);
+                        (visited | 1 << next, path_len + dist, path)
+                    });
+                // Hikes that already reached the target are carried along unchanged
+                let finished = (current == target).then(|| (*visited, *path_len, path.clone()));
+                children.chain(finished)
+            })
+            .partition(|(_, _, path)| *path.last().unwrap() == target);
+        let expanded = open.len();
+        branches = open.into_iter().chain(done).collect();
+        if expanded == 0 {
+            break;
+        }
+    }
+
+    let best = branches
+        .into_par_iter()
+        .map(|(visited, path_len, mut path)| {
+            let mut best = (0, vec![]);
+            check_all_paths(&graph, target, visited, path_len, &mut path, &mut best);
+            best
+        })
+        .filter(|(_, path)| !path.is_empty())
+        .max_by_key(|(len, _)| *len)
+        .expect("No path to the end found");
+
+    let mut junctions = best
+        .1
+        .into_iter()
+        .map(|id| graph.junctions[id])
+        .collect::<Vec<_>>();
+    if target != end {
+        junctions.push(graph.junctions[end]);
+    }
+    let hike = Hike::along(hiking_map, junctions, true);
+    debug_assert_eq!(hike.length, best.0 + final_stretch);
+    hike
 }
 
 #[aoc(day23, part1)]
-fn part1(input: &HikingMap) -> usize {
-    longest_path_with_slopes(input)
+pub(crate) fn part1(input: &HikingMap) -> usize {
+    longest_path_with_slopes(input, Endpoints::detect(input).unwrap()).length
 }
 
 #[aoc(day23, part2)]
-fn part2(input: &HikingMap) -> usize {
-    longest_path_without_slopes(input)
+pub(crate) fn part2(input: &HikingMap) -> usize {
+    longest_path_without_slopes(input, Endpoints::detect(input).unwrap()).length
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
 
     const EXAMPLE: &str = r#"#.#####################
 #.......#########...###
@@ -267,20 +523,83 @@ mod tests {
 #.....###...###...#...#
 #####################.#"#;
 
-    const INPUT: &str = include_str!("../input/2023/day23.txt");
-
     #[test]
     fn part1_example() {
         assert_eq!(part1(&parse(EXAMPLE)), 94);
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(INPUT)), 2362);
+    fn part2_example() {
+        assert_eq!(part2(&parse(EXAMPLE)), 154);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse(INPUT)), 154);
+    fn part2_example_route() {
+        let map = parse(EXAMPLE);
+        let endpoints = Endpoints::detect(&map).unwrap();
+        let hike = longest_path_without_slopes(&map, endpoints);
+        assert_eq!(hike.junctions.first(), Some(&endpoints.start));
+        assert_eq!(hike.junctions.last(), Some(&endpoints.end));
+        let edges = calculate_edges(&map, endpoints, true);
+        let walked: usize = hike.junctions.windows(2).map(|w| edges[&w[0]][&w[1]]).sum();
+        assert_eq!(walked, hike.length);
+        assert_eq!(hike.junctions.iter().unique().count(), hike.junctions.len());
+    }
+
+    #[test]
+    fn part1_example_route() {
+        let map = parse(EXAMPLE);
+        let hike = longest_path_with_slopes(&map, Endpoints::detect(&map).unwrap());
+        assert_eq!(hike.tiles.len(), 95);
+        assert_eq!(hike.tiles.iter().unique().count(), hike.tiles.len());
+        assert!(hike
+            .tiles
+            .windows(2)
+            .all(|w| w[0].rectilinear_dist(&w[1]) == 1));
+        let rendered = hike.render(&map);
+        assert_eq!(rendered.matches('O').count(), 94);
+        assert_eq!(
+            rendered.lines().take(3).collect::<Vec<_>>(),
+            vec![
+                "#S#####################",
+                "#OOOOOOO#########...###",
+                "#######O#########.#.###",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_tiles() {
+        assert_eq!(
+            parse_map("#.#\n#x#\n#.#").unwrap_err(),
+            TrailError::UnknownTile {
+                tile: 'x',
+                at: Point { x: 1, y: 1 }
+            }
+        );
+    }
+
+    #[test]
+    fn endpoints() {
+        let map = parse(EXAMPLE);
+        assert_eq!(
+            Endpoints::detect(&map),
+            Ok(Endpoints {
+                start: Point { x: 1, y: 0 },
+                end: Point { x: 21, y: 22 }
+            })
+        );
+        let open_side = parse("#.#\n...\n#.#");
+        assert!(matches!(
+            Endpoints::detect(&open_side),
+            Err(TrailError::AmbiguousEndpoints(openings)) if openings.len() == 4
+        ));
+        let sideways =
+            Endpoints::new(&open_side, Point { x: 0, y: 1 }, Point { x: 2, y: 1 }).unwrap();
+        assert_eq!(longest_path_without_slopes(&open_side, sideways).length, 2);
+        assert_eq!(
+            Endpoints::new(&open_side, Point { x: 0, y: 0 }, Point { x: 1, y: 2 }),
+            Err(TrailError::NotOnTrail(Point { x: 0, y: 0 }))
+        );
     }
 }
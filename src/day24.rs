@@ -4,7 +4,7 @@ use itertools::Itertools;
 use z3::ast::{Ast, Int};
 
 #[aoc_generator(day24)]
-fn parse(input: &str) -> Vec<Point3WithVel> {
+pub(crate) fn parse(input: &str) -> Vec<Point3WithVel> {
     input
         .lines()
         .map(|line| {
@@ -72,12 +72,12 @@ fn throw_rock(hailstones: &[Point3WithVel]) -> i64 {
 }
 
 #[aoc(day24, part1)]
-fn part1(input: &[Point3WithVel]) -> usize {
+pub(crate) fn part1(input: &[Point3WithVel]) -> usize {
     find_intersections(input, 200000000000000.0, 400000000000000.0)
 }
 
 #[aoc(day24, part2)]
-fn part2(input: &[Point3WithVel]) -> i64 {
+pub(crate) fn part2(input: &[Point3WithVel]) -> i64 {
     throw_rock(input)
 }
 
@@ -91,18 +91,12 @@ mod tests {
 12, 31, 28 @ -1, -2, -1
 20, 19, 15 @  1, -5, -3"#;
 
-    const INPUT: &str = include_str!("../input/2023/day24.txt");
     #[test]
     fn finds_intersection() {
         let lines = parse(EXAMPLE);
         assert_eq!(find_intersections(&lines, 7.0, 27.0), 2);
     }
 
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(INPUT)), 20336);
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 0);
@@ -5,7 +5,7 @@ use aoc_runner_derive::{aoc, aoc_generator};
 type Graph = HashMap<u16, HashSet<u16>>;
 
 #[aoc_generator(day25)]
-fn parse(input: &str) -> Graph {
+pub(crate) fn parse(input: &str) -> Graph {
     let mut node_ids = HashMap::new();
     let mut nodes = HashMap::new();
     for line in input.lines() {
@@ -75,7 +75,7 @@ fn connected_count(graph: &Graph) -> usize {
     visited.len()
 }
 #[aoc(day25, part1)]
-fn part1(input: &Graph) -> usize {
+pub(crate) fn part1(input: &Graph) -> usize {
     let mut components = input.clone();
     for i in 1..input.len() {
         let paths = (0..3)
@@ -107,7 +107,7 @@ fn part1(input: &Graph) -> usize {
 }
 
 #[aoc(day25, part2)]
-fn part2(input: &Graph) -> usize {
+pub(crate) fn part2(input: &Graph) -> usize {
     0
 }
 
@@ -128,14 +128,8 @@ lsr: lhk
 rzs: qnr cmg lsr rsh
 frs: qnr lhk lsr"#;
 
-    const INPUT: &str = include_str!("../input/2023/day25.txt");
     #[test]
     fn part1_example() {
         assert_eq!(part1(&parse(EXAMPLE)), 54);
     }
-
-    #[test]
-    fn solves_part1() {
-        assert_eq!(part1(&parse(INPUT)), 527790);
-    }
 }
@@ -2,72 +2,168 @@ use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::HashMap;
 pub type Point = (usize, usize);
 
-pub struct Gear {
-    pub symbol: char,
-    pub part_numbers: Vec<u32>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchematicError {
+    NumberTooLarge { row: usize, start: usize },
 }
 
-#[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> HashMap<Point, Gear> {
-    let mut gears: HashMap<Point, Gear> = HashMap::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, symbol) in line.chars().enumerate() {
-            if symbol != '.' && !symbol.is_ascii_digit() {
-                gears.entry((x, y)).or_insert(Gear {
-                    symbol,
-                    part_numbers: vec![],
-                });
+impl std::fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchematicError::NumberTooLarge { row, start } => {
+                write!(
+                    f,
+                    "number at row {row}, column {start} does not fit in a u64"
+                )
             }
         }
     }
+}
+
+/// A number in the schematic, spanning columns `start..end` of `row`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartNumber {
+    pub value: u64,
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub position: Point,
+}
+
+/// Every number and symbol in the engine schematic, indexed by which ones touch,
+/// including diagonally.
+#[derive(Debug, Clone, Default)]
+pub struct Schematic {
+    pub numbers: Vec<PartNumber>,
+    pub symbols: Vec<Symbol>,
+    symbols_by_number: Vec<Vec<usize>>,
+    numbers_by_symbol: Vec<Vec<usize>>,
+}
 
-    for (y, line) in input.lines().enumerate() {
-        let mut number_digits = vec![];
-        let mut digit_start_x = None;
-        for (x, symb) in line.chars().enumerate() {
-            if let Some(n) = symb.to_digit(10) {
-                number_digits.push(n);
-                if digit_start_x.is_none() {
-                    digit_start_x = Some(x);
+impl Schematic {
+    /// Periods and whitespace are empty space. Lines are not trimmed, so indentation keeps
+    /// every column where it is.
+    pub fn parse(input: &str) -> Result<Schematic, SchematicError> {
+        let mut numbers = vec![];
+        let mut symbols = vec![];
+        for (y, line) in input.lines().enumerate() {
+            let mut current: Option<PartNumber> = None;
+            for (x, c) in line.chars().chain(std::iter::once('.')).enumerate() {
+                match (c.to_digit(10), current.as_mut()) {
+                    (Some(digit), Some(number)) => {
+                        number.value = number
+                            .value
+                            .checked_mul(10)
+                            .and_then(|v| v.checked_add(digit as u64))
+                            .ok_or(SchematicError::NumberTooLarge {
+                                row: y,
+                                start: number.start,
+                            })?;
+                        number.end = x + 1;
+                    }
+                    (Some(digit), None) => {
+                        current = Some(PartNumber {
+                            value: digit as u64,
+                            row: y,
+                            start: x,
+                            end: x + 1,
+                        })
+                    }
+                    (None, _) => {
+                        numbers.extend(current.take());
+                        if c != '.' && !c.is_whitespace() {
+                            symbols.push(Symbol {
+                                symbol: c,
+                                position: (x, y),
+                            });
+                        }
+                    }
                 }
             }
-            if symb.to_digit(10).is_none() || x == line.chars().count() - 1 {
-                if !number_digits.is_empty() {
-                    let number = number_digits.iter().copied().fold(0, |acc, n| acc * 10 + n);
-                    if let Some(xs) = digit_start_x {
-                        for gx in xs.saturating_sub(1)..=x {
-                            for yoff in -1..=1 {
-                                let gy = y.saturating_add_signed(yoff);
-                                if let Some(g) = gears.get_mut(&(gx, gy)) {
-                                    g.part_numbers.push(number)
-                                }
-                            }
-                        }
+        }
+
+        let symbol_at: HashMap<Point, usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.position, i))
+            .collect();
+        let mut symbols_by_number = vec![vec![]; numbers.len()];
+        let mut numbers_by_symbol = vec![vec![]; symbols.len()];
+        for (n, number) in numbers.iter().enumerate() {
+            for y in number.row.saturating_sub(1)..=number.row + 1 {
+                for x in number.start.saturating_sub(1)..=number.end {
+                    if let Some(&s) = symbol_at.get(&(x, y)) {
+                        symbols_by_number[n].push(s);
+                        numbers_by_symbol[s].push(n);
                     }
                 }
-                number_digits.clear();
-                digit_start_x = None;
             }
         }
+        Ok(Schematic {
+            numbers,
+            symbols,
+            symbols_by_number,
+            numbers_by_symbol,
+        })
+    }
+
+    pub fn numbers_adjacent_to(&self, symbol: usize) -> impl Iterator<Item = &PartNumber> {
+        self.numbers_by_symbol[symbol]
+            .iter()
+            .map(|&n| &self.numbers[n])
+    }
+
+    pub fn symbols_adjacent_to(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols_by_number[number]
+            .iter()
+            .map(|&s| &self.symbols[s])
+    }
+
+    /// Numbers next to at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &PartNumber> {
+        self.numbers
+            .iter()
+            .zip(&self.symbols_by_number)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+
+    pub fn isolated_numbers(&self) -> impl Iterator<Item = &PartNumber> {
+        self.numbers
+            .iter()
+            .zip(&self.symbols_by_number)
+            .filter(|(_, symbols)| symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+
+    /// The product of the numbers around each `symbol` that touches exactly `count` numbers.
+    pub fn gear_ratios(&self, symbol: char, count: usize) -> impl Iterator<Item = u64> + '_ {
+        (0..self.symbols.len())
+            .filter(move |&s| {
+                self.symbols[s].symbol == symbol && self.numbers_by_symbol[s].len() == count
+            })
+            .map(|s| self.numbers_adjacent_to(s).map(|n| n.value).product())
     }
-    gears
+}
+
+#[aoc_generator(day3)]
+pub fn input_generator(input: &str) -> Schematic {
+    Schematic::parse(input).unwrap_or_else(|e| panic!("Invalid schematic: {e}"))
 }
 
 #[aoc(day3, part1)]
-pub fn solve_part1(input: &HashMap<Point, Gear>) -> u32 {
-    input
-        .values()
-        .map(|v| v.part_numbers.iter().sum::<u32>())
-        .sum()
+pub fn solve_part1(input: &Schematic) -> u64 {
+    input.part_numbers().map(|n| n.value).sum()
 }
 
 #[aoc(day3, part2)]
-pub fn solve_part2(input: &HashMap<Point, Gear>) -> u64 {
-    input
-        .values()
-        .filter(|g| g.symbol == '*' && g.part_numbers.len() == 2)
-        .map(|g| g.part_numbers.iter().fold(1, |acc, n| acc * (*n as u64)))
-        .sum()
+pub fn solve_part2(input: &Schematic) -> u64 {
+    input.gear_ratios('*', 2).sum()
 }
 
 #[cfg(test)]
@@ -109,4 +205,37 @@ mod tests {
         let input = input_generator(input);
         assert_eq!(solve_part2(&input), 467835);
     }
+
+    #[test]
+    pub fn adjacency_queries() {
+        let schematic = input_generator("467..114..\n...*......\n..35..633.\n......#...");
+        let values =
+            |numbers: Vec<&PartNumber>| numbers.iter().map(|n| n.value).collect::<Vec<_>>();
+        assert_eq!(values(schematic.isolated_numbers().collect()), vec![114]);
+        assert_eq!(
+            values(schematic.numbers_adjacent_to(0).collect()),
+            vec![467, 35]
+        );
+        assert_eq!(
+            schematic.symbols_adjacent_to(3).collect::<Vec<_>>(),
+            vec![&Symbol {
+                symbol: '#',
+                position: (6, 3)
+            }]
+        );
+        assert_eq!(schematic.gear_ratios('#', 1).collect::<Vec<_>>(), vec![633]);
+        assert_eq!(schematic.gear_ratios('*', 3).count(), 0);
+    }
+
+    #[test]
+    pub fn keeps_indented_columns() {
+        let schematic = input_generator("  12\n   *\n 7  ");
+        assert_eq!(schematic.symbols[0].position, (3, 1));
+        assert_eq!(schematic.part_numbers().count(), 1);
+        assert_eq!(schematic.isolated_numbers().next().unwrap().value, 7);
+        assert!(matches!(
+            Schematic::parse("123456789012345678901234567890*"),
+            Err(SchematicError::NumberTooLarge { row: 0, start: 0 })
+        ));
+    }
 }
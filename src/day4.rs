@@ -1,7 +1,4 @@
-use std::{
-    cmp::min,
-    collections::{HashMap, HashSet},
-};
+use std::collections::HashSet;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
@@ -12,36 +9,120 @@ pub struct Card {
     pub own: HashSet<u32>,
 }
 
-#[aoc_generator(day4)]
-pub fn generator(input: &str) -> Vec<Card> {
-    input
-        .lines()
-        .enumerate()
-        .map(|(id, line)| {
-            let numbers = line
-                .split(':')
-                .nth(1)
-                .map(|f| f.split('|'))
-                .unwrap()
-                .collect::<Vec<_>>();
-            let winning = numbers.first()
-                .unwrap()
+impl Card {
+    fn parse(line: &str) -> Result<Card, CardError> {
+        let invalid = || CardError::InvalidCard(line.trim().to_string());
+        let (label, numbers) = line.split_once(':').ok_or_else(invalid)?;
+        let id = label
+            .trim()
+            .strip_prefix("Card")
+            .and_then(|id| id.trim().parse::<usize>().ok())
+            .ok_or_else(invalid)?;
+        let (winning, own) = numbers.split_once('|').ok_or_else(invalid)?;
+        let parse_numbers = |numbers: &str| {
+            numbers
                 .split_whitespace()
-                .map(|x| x.parse::<u32>().unwrap())
-                .collect::<HashSet<_>>();
-            let own = numbers
-                .get(1)
-                .unwrap()
-                .split_whitespace()
-                .map(|x| x.parse::<u32>().unwrap())
-                .collect::<HashSet<_>>();
-            Card {
-                id: id + 1,
-                winning,
-                own,
-            }
+                .map(|x| x.parse::<u32>().map_err(|_| invalid()))
+                .collect::<Result<HashSet<_>, _>>()
+        };
+        Ok(Card {
+            id,
+            winning: parse_numbers(winning)?,
+            own: parse_numbers(own)?,
         })
-        .collect()
+    }
+
+    pub fn matches(&self) -> usize {
+        self.own.intersection(&self.winning).count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardError {
+    InvalidCard(String),
+    Duplicate(usize),
+    OutOfOrder { previous: usize, found: usize },
+    Gap { expected: usize, found: usize },
+    TooManyCopies(usize),
+}
+
+impl std::fmt::Display for CardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardError::InvalidCard(line) => write!(f, "invalid card {line:?}"),
+            CardError::Duplicate(id) => write!(f, "card {id} appears more than once"),
+            CardError::OutOfOrder { previous, found } => {
+                write!(f, "card {found} comes after card {previous}")
+            }
+            CardError::Gap { expected, found } => {
+                write!(f, "expected card {expected}, found card {found}")
+            }
+            CardError::TooManyCopies(id) => write!(f, "copies of card {id} overflow a u64"),
+        }
+    }
+}
+
+/// Parses the cards, which must be numbered from 1 in order with no gaps since winning
+/// copies refers to the cards by position.
+pub fn parse_cards(input: &str) -> Result<Vec<Card>, CardError> {
+    let cards = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Card::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut seen = HashSet::new();
+    if let Some(card) = cards.iter().find(|card| !seen.insert(card.id)) {
+        return Err(CardError::Duplicate(card.id));
+    }
+    if let Some(pair) = cards.windows(2).find(|pair| pair[0].id > pair[1].id) {
+        return Err(CardError::OutOfOrder {
+            previous: pair[0].id,
+            found: pair[1].id,
+        });
+    }
+    if let Some((expected, card)) = (1..)
+        .zip(&cards)
+        .find(|(expected, card)| card.id != *expected)
+    {
+        return Err(CardError::Gap {
+            expected,
+            found: card.id,
+        });
+    }
+    Ok(cards)
+}
+
+#[aoc_generator(day4)]
+pub fn generator(input: &str) -> Vec<Card> {
+    parse_cards(input).unwrap_or_else(|e| panic!("Invalid scratchcards: {e}"))
+}
+
+/// How many copies of each card end up being scratched, originals included. Copies can
+/// double with every card, so this fails once a count no longer fits in a u64.
+pub fn copies(cards: &[Card]) -> Result<Vec<u64>, CardError> {
+    // Each card adds its copies to a run of the following cards, tracked as a difference
+    // array split into arrivals and departures so it stays unsigned
+    let mut arriving = vec![0u64; cards.len() + 1];
+    let mut leaving = vec![0u64; cards.len() + 1];
+    let mut current = 0u64;
+    let mut copies = Vec::with_capacity(cards.len());
+    for (i, card) in cards.iter().enumerate() {
+        let too_many = || CardError::TooManyCopies(card.id);
+        // Departures may only be covered by this card's arrivals, so add before subtracting
+        current = (current as u128 + arriving[i] as u128 - leaving[i] as u128)
+            .try_into()
+            .map_err(|_| too_many())?;
+        let copies_of_me = current.checked_add(1).ok_or_else(too_many)?;
+        copies.push(copies_of_me);
+        let end = (i + 1 + card.matches()).min(cards.len());
+        arriving[i + 1] = arriving[i + 1]
+            .checked_add(copies_of_me)
+            .ok_or_else(too_many)?;
+        leaving[end] = leaving[end]
+            .checked_add(copies_of_me)
+            .ok_or_else(too_many)?;
+    }
+    Ok(copies)
 }
 
 #[aoc(day4, part1)]
@@ -49,7 +130,7 @@ pub fn solve_part1(input: &[Card]) -> u32 {
     input
         .iter()
         .map(|c| {
-            let intersect = c.own.intersection(&c.winning).count();
+            let intersect = c.matches();
             if intersect == 0 {
                 return 0;
             }
@@ -59,29 +140,11 @@ pub fn solve_part1(input: &[Card]) -> u32 {
 }
 
 #[aoc(day4, part2)]
-pub fn solve_part2(input: &[Card]) -> u32 {
-    let mut copies: HashMap<u32, u32> = HashMap::new();
-    let max_id = input.iter().map(|c| c.id).max().unwrap();
-    for card in input {
-        let winning = card.own.intersection(&card.winning).count();
-        let copies_of_me = *copies
-            .entry(card.id as u32)
-            .and_modify(|c| *c += 1)
-            .or_insert(1);
-        if winning == 0 {
-            continue;
-        }
-
-        let start_index = card.id + 1;
-        let end_index = min(start_index + winning - 1, max_id);
-        for i in start_index..=end_index {
-            copies
-                .entry(i as u32)
-                .and_modify(|c| *c += copies_of_me)
-                .or_insert(copies_of_me);
-        }
-    }
-    copies.values().sum()
+pub fn solve_part2(input: &[Card]) -> u64 {
+    copies(input)
+        .ok()
+        .and_then(|copies| copies.into_iter().try_fold(0u64, u64::checked_add))
+        .unwrap_or_else(|| panic!("Too many scratchcards to count in a u64"))
 }
 
 #[cfg(test)]
@@ -103,13 +166,6 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
         assert_eq!(solve_part1(&input), 13)
     }
 
-    #[test]
-    pub fn part1() {
-        assert_eq!(
-            solve_part1(&generator(include_str!("../input/2023/day4.txt"))),
-            15205
-        );
-    }
     #[test]
     pub fn can_handle_test_input_part2() {
         let input = r#"
@@ -124,11 +180,68 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
         let input = generator(input);
         assert_eq!(solve_part2(&input), 30);
     }
+
+    #[test]
+    pub fn copy_breakdown() {
+        let input = generator(
+            "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11",
+        );
+        assert_eq!(copies(&input), Ok(vec![1, 2, 4, 8, 14, 1]));
+    }
+
+    #[test]
+    pub fn reports_copy_overflow() {
+        // Every card wins a copy of every later card, doubling the count each time
+        let numbers = (1..=70)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let cards = (1..=70)
+            .map(|id| format!("Card {id}: {numbers} | {numbers}\n"))
+            .collect::<String>();
+        let cards = generator(&cards);
+        assert!(matches!(copies(&cards), Err(CardError::TooManyCopies(_))));
+        assert_eq!(copies(&cards[..60]).unwrap()[59], 1 << 59);
+    }
+
     #[test]
-    pub fn part2() {
+    pub fn validates_card_ids() {
+        let card = |id: usize| format!("Card {id}: 1 2 | 3 4\n");
+        let ids = |ids: &[usize]| ids.iter().map(|&id| card(id)).collect::<String>();
+        assert!(parse_cards(&ids(&[1, 2, 3])).is_ok());
+        assert_eq!(
+            parse_cards(&ids(&[1, 3])).unwrap_err(),
+            CardError::Gap {
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!(
+            parse_cards(&ids(&[1, 2, 2])).unwrap_err(),
+            CardError::Duplicate(2)
+        );
+        assert_eq!(
+            parse_cards(&ids(&[2, 1])).unwrap_err(),
+            CardError::OutOfOrder {
+                previous: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            parse_cards(&ids(&[2, 3])).unwrap_err(),
+            CardError::Gap {
+                expected: 1,
+                found: 2
+            }
+        );
         assert_eq!(
-            solve_part2(&generator(include_str!("../input/2023/day4.txt"))),
-            6189740
+            parse_cards("Card 1: 1 2 3").unwrap_err(),
+            CardError::InvalidCard("Card 1: 1 2 3".to_string())
         );
     }
 }
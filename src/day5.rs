@@ -1,106 +1,360 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-pub type SeedToLocations = (Vec<u64>, Vec<Vec<Vec<u64>>>);
+/// `len` consecutive values starting at `src` map onto the ones starting at `dst`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Segment {
+    pub src: u64,
+    pub dst: u64,
+    pub len: u64,
+}
 
-#[aoc_generator(day5)]
-pub fn read_input(input: &str) -> SeedToLocations {
-    let mut blocks = input.split("\n\n");
-    let seeds: Vec<u64> = blocks
-        .next()
-        .unwrap()
-        .strip_prefix("seeds: ")
-        .unwrap()
-        .split_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
-    (
-        seeds,
-        blocks
-            .map(|s| {
-                s.lines()
-                    .skip(1)
-                    .map(|l| l.split_whitespace().map(|s| s.parse().unwrap()).collect())
-                    .collect()
-            })
-            .collect(),
-    )
-}
-
-fn apply_section((mut low, mut ilen): (u64, u64), section: &[Vec<u64>]) -> Vec<(u64, u64)> {
-    let mut seed = Vec::new();
-    for mapping in section {
-        let (destination, origin, length) = (mapping[0], mapping[1], mapping[2]);
-        if origin >= low && origin < low + ilen {
-            if origin > low {
-                seed.push((low, origin - low));
-                ilen -= origin - low;
-                low = origin;
+impl Segment {
+    fn src_end(&self) -> u64 {
+        self.src + self.len
+    }
+}
+
+/// A piecewise offset map over `u64`, which leaves any value outside its segments unchanged.
+/// Segments are kept sorted by source and never overlap.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeMap {
+    segments: Vec<Segment>,
+}
+
+impl RangeMap {
+    pub fn new(mut segments: Vec<Segment>) -> RangeMap {
+        segments.retain(|s| s.len > 0);
+        segments.sort_unstable();
+        RangeMap { segments }
+    }
+
+    /// The map as segments covering every value below `u64::MAX`, including the unchanged gaps.
+    fn pieces(&self) -> Vec<Segment> {
+        let mut pieces = Vec::with_capacity(self.segments.len() * 2 + 1);
+        let mut next = 0;
+        for segment in &self.segments {
+            if segment.src > next {
+                pieces.push(Segment {
+                    src: next,
+                    dst: next,
+                    len: segment.src - next,
+                });
             }
-            let l = length.min(ilen);
-            seed.push((destination, l));
-            low += l;
-            ilen -= l;
-        } else if origin < low && origin + length > low {
-            let l = (origin + length - low).min(ilen);
-            seed.push((low - origin + destination, l));
-            low += l;
-            ilen -= l;
+            pieces.push(*segment);
+            next = segment.src_end();
         }
+        if next < u64::MAX {
+            pieces.push(Segment {
+                src: next,
+                dst: next,
+                len: u64::MAX - next,
+            });
+        }
+        pieces
     }
-    if ilen > 0 {
-        seed.push((low, ilen));
+
+    pub fn get(&self, value: u64) -> u64 {
+        let i = self.segments.partition_point(|s| s.src <= value);
+        match i.checked_sub(1).map(|i| self.segments[i]) {
+            Some(s) if value < s.src_end() => value - s.src + s.dst,
+            _ => value,
+        }
     }
-    seed
-}
-fn apply_sections(interval: (u64, u64), sections: &[Vec<Vec<u64>>]) -> Vec<(u64, u64)> {
-    if sections.is_empty() {
-        vec![interval]
-    } else {
-        apply_section(interval, &sections[0])
+
+    /// Where the `len` values from `start` end up, as `(start, len)` intervals in source order.
+    pub fn get_interval(&self, start: u64, len: u64) -> Vec<(u64, u64)> {
+        let end = start + len;
+        let first = self.segments.partition_point(|s| s.src_end() <= start);
+        let mut mapped = Vec::new();
+        let mut low = start;
+        for segment in self.segments[first..].iter().take_while(|s| s.src < end) {
+            if segment.src > low {
+                mapped.push((low, segment.src - low));
+                low = segment.src;
+            }
+            let high = end.min(segment.src_end());
+            mapped.push((low - segment.src + segment.dst, high - low));
+            low = high;
+        }
+        if low < end {
+            mapped.push((low, end - low));
+        }
+        mapped
+    }
+
+    /// Every value that maps onto `value`.
+    pub fn preimage(&self, value: u64) -> Vec<u64> {
+        let mut sources = self
+            .segments
+            .iter()
+            .filter(|s| s.dst <= value && value - s.dst < s.len)
+            .map(|s| value - s.dst + s.src)
+            .collect::<Vec<_>>();
+        if self.get(value) == value {
+            sources.push(value);
+        }
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    /// The lowest output whose preimage contains a value matching `predicate`.
+    ///
+    /// Rather than trying every output, this only tries the starts of the map's pieces and the
+    /// images of `breakpoints`, the values where `predicate` may change. Within a piece outputs
+    /// grow with their inputs, so the lowest match is always one of those.
+    pub fn lowest_output_where(
+        &self,
+        breakpoints: impl IntoIterator<Item = u64>,
+        predicate: impl Fn(u64) -> bool,
+    ) -> Option<u64> {
+        let mut candidates = self
+            .pieces()
+            .into_iter()
+            .map(|piece| piece.dst)
+            .chain(breakpoints.into_iter().map(|b| self.get(b)))
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
             .into_iter()
-            .flat_map(|i| apply_sections(i, &sections[1..]))
-            .collect()
+            .find(|&output| self.preimage(output).into_iter().any(&predicate))
+    }
+
+    /// Swaps sources and destinations, which undoes the map as long as it is one-to-one.
+    pub fn inverse(&self) -> RangeMap {
+        RangeMap::new(
+            self.segments
+                .iter()
+                .map(|s| Segment {
+                    src: s.dst,
+                    dst: s.src,
+                    len: s.len,
+                })
+                .collect(),
+        )
     }
 }
 
-#[aoc(day5, part1)]
-pub fn solve_part1((seeds, maps): &SeedToLocations) -> u64 {
-    seeds
-        .clone()
+/// The map that applies `first` and then `second`.
+pub fn compose(first: &RangeMap, second: &RangeMap) -> RangeMap {
+    let segments = first
+        .pieces()
         .into_iter()
-        .map(|seed| {
-            maps.iter().fold(seed, |s, map| {
-                for m in map {
-                    if s >= m[1] && s < m[1] + m[2] {
-                        return s - m[1] + m[0];
+        .flat_map(|piece| {
+            let mut src = piece.src;
+            second
+                .get_interval(piece.dst, piece.len)
+                .into_iter()
+                .map(move |(dst, len)| {
+                    let segment = Segment { src, dst, len };
+                    src += len;
+                    segment
+                })
+        })
+        .filter(|s| s.src != s.dst)
+        .collect();
+    RangeMap::new(segments)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlmanacError {
+    MissingSeeds,
+    InvalidHeader(String),
+    InvalidRange(String),
+    OverlappingRanges {
+        from: String,
+        first: Segment,
+        second: Segment,
+    },
+    DuplicateMap(String),
+    /// The chain from seed to location stops at this category.
+    BrokenChain(String),
+    /// A map that is not part of the chain from seed to location.
+    UnusedMap(String),
+    NoPath {
+        from: String,
+        to: String,
+    },
+}
+
+impl std::fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlmanacError::MissingSeeds => write!(f, "almanac does not start with seeds"),
+            AlmanacError::InvalidHeader(header) => write!(f, "invalid map header {header:?}"),
+            AlmanacError::InvalidRange(line) => write!(f, "invalid range {line:?}"),
+            AlmanacError::OverlappingRanges {
+                from,
+                first,
+                second,
+            } => write!(f, "{from} ranges {first:?} and {second:?} overlap"),
+            AlmanacError::DuplicateMap(from) => write!(f, "more than one map from {from}"),
+            AlmanacError::BrokenChain(category) => write!(f, "no map from {category}"),
+            AlmanacError::UnusedMap(from) => write!(f, "map from {from} is never reached"),
+            AlmanacError::NoPath { from, to } => write!(f, "no path from {from} to {to}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryMap {
+    pub from: String,
+    pub to: String,
+    pub map: RangeMap,
+}
+
+impl CategoryMap {
+    fn parse(section: &str) -> Result<CategoryMap, AlmanacError> {
+        let mut lines = section.lines();
+        let header = lines.next().unwrap_or_default().trim();
+        let (from, to) = header
+            .strip_suffix(" map:")
+            .and_then(|names| names.split_once("-to-"))
+            .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+            .ok_or_else(|| AlmanacError::InvalidHeader(header.to_string()))?;
+        let mut segments = lines
+            .map(|line| {
+                let values = line
+                    .split_whitespace()
+                    .map(|v| v.parse::<u64>())
+                    .collect::<Result<Vec<_>, _>>();
+                match values.as_deref() {
+                    // Both ends must fit, since the map is also followed and inverted by range
+                    Ok(&[dst, src, len])
+                        if src.checked_add(len).is_some() && dst.checked_add(len).is_some() =>
+                    {
+                        Ok(Segment { src, dst, len })
                     }
+                    _ => Err(AlmanacError::InvalidRange(line.to_string())),
                 }
-                s
             })
+            .collect::<Result<Vec<_>, _>>()?;
+        segments.sort_unstable();
+        if let Some(pair) = segments
+            .windows(2)
+            .find(|pair| pair[0].src_end() > pair[1].src && pair[0].len > 0)
+        {
+            return Err(AlmanacError::OverlappingRanges {
+                from: from.to_string(),
+                first: pair[0],
+                second: pair[1],
+            });
+        }
+        Ok(CategoryMap {
+            from: from.to_string(),
+            to: to.to_string(),
+            map: RangeMap::new(segments),
         })
+    }
+}
+
+/// The seeds and the maps between categories, ordered as the chain from seed to location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Almanac {
+    pub seeds: Vec<u64>,
+    maps: Vec<CategoryMap>,
+}
+
+impl Almanac {
+    pub fn parse(input: &str) -> Result<Almanac, AlmanacError> {
+        let mut blocks = input.trim().split("\n\n");
+        let seeds = blocks
+            .next()
+            .and_then(|line| line.trim().strip_prefix("seeds:"))
+            .ok_or(AlmanacError::MissingSeeds)?
+            .split_whitespace()
+            .map(|s| s.parse().map_err(|_| AlmanacError::MissingSeeds))
+            .collect::<Result<Vec<u64>, _>>()?;
+        let mut unordered = blocks
+            .map(CategoryMap::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut maps = Vec::with_capacity(unordered.len());
+        let mut category = "seed".to_string();
+        while category != "location" {
+            let mut from_here = unordered.iter().filter(|m| m.from == category);
+            if from_here.nth(1).is_some() {
+                return Err(AlmanacError::DuplicateMap(category));
+            }
+            let next = unordered
+                .iter()
+                .position(|m| m.from == category)
+                .ok_or(AlmanacError::BrokenChain(category))?;
+            let map = unordered.swap_remove(next);
+            category = map.to.clone();
+            maps.push(map);
+        }
+        if let Some(unused) = unordered.first() {
+            return Err(AlmanacError::UnusedMap(unused.from.clone()));
+        }
+        Ok(Almanac { seeds, maps })
+    }
+
+    /// The single map taking values of category `from` to category `to`.
+    pub fn map(&self, from: &str, to: &str) -> Result<RangeMap, AlmanacError> {
+        let no_path = || AlmanacError::NoPath {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        let first = self
+            .maps
+            .iter()
+            .position(|m| m.from == from)
+            .ok_or_else(no_path)?;
+        let last = self.maps[first..]
+            .iter()
+            .position(|m| m.to == to)
+            .ok_or_else(no_path)?;
+        Ok(self.maps[first..=first + last]
+            .iter()
+            .fold(RangeMap::default(), |acc, m| compose(&acc, &m.map)))
+    }
+}
+
+#[aoc_generator(day5)]
+pub fn read_input(input: &str) -> Almanac {
+    Almanac::parse(input).unwrap_or_else(|e| panic!("Invalid almanac: {e}"))
+}
+
+#[aoc(day5, part1)]
+pub fn solve_part1(input: &Almanac) -> u64 {
+    let almanac = input.map("seed", "location").unwrap();
+    input
+        .seeds
+        .iter()
+        .map(|&seed| almanac.get(seed))
         .min()
         .unwrap()
 }
 
 #[aoc(day5, part2)]
-pub fn solve_part2(input: &SeedToLocations) -> u64 {
-    let (seeds, mut maps) = input.clone();
-    for section in &mut maps {
-        section.sort_unstable_by_key(|i| i[1]);
-    }
-    seeds
+pub fn solve_part2(input: &Almanac) -> u64 {
+    let almanac = input.map("seed", "location").unwrap();
+    input
+        .seeds
         .chunks_exact(2)
-        .map(|chunk| {
-            apply_sections((chunk[0], chunk[1]), &maps)
-                .into_iter()
-                .min_by_key(|(l, _)| *l)
-                .unwrap()
-                .0
-        })
+        .flat_map(|chunk| almanac.get_interval(chunk[0], chunk[1]))
+        .map(|(start, _)| start)
         .min()
         .unwrap()
 }
 
+/// Finds the answer to part 2 from the location side, as a cross-check on the forward search.
+pub fn solve_part2_reverse(input: &Almanac) -> u64 {
+    let almanac = input.map("seed", "location").unwrap();
+    let seed_ranges = input
+        .seeds
+        .chunks_exact(2)
+        .map(|chunk| chunk[0]..chunk[0] + chunk[1])
+        .collect::<Vec<_>>();
+    almanac
+        .lowest_output_where(seed_ranges.iter().map(|r| r.start), |seed| {
+            seed_ranges.iter().any(|r| r.contains(&seed))
+        })
+        .unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -146,13 +400,6 @@ humidity-to-location map:
         assert_eq!(solve_part1(&maps), 35);
     }
 
-    #[test]
-    pub fn solves_part1() {
-        let input = include_str!("../input/2023/day5.txt").trim();
-        let maps = read_input(input);
-        assert_eq!(solve_part1(&maps), 251346198);
-    }
-
     #[test]
     pub fn part_2_works_for_test_input() {
         let input = r#"
@@ -195,10 +442,146 @@ humidity-to-location map:
     }
 
     #[test]
-    pub fn solves_part_2() {
+    pub fn composed_map_matches_applying_each_map() {
         let input = include_str!("../input/2023/day5.txt").trim();
-        let maps = read_input(input);
+        let input = read_input(input);
+        let almanac = input.map("seed", "location").unwrap();
+        for &seed in &input.seeds {
+            let stepwise = input.maps.iter().fold(seed, |value, m| m.map.get(value));
+            assert_eq!(almanac.get(seed), stepwise);
+            assert_eq!(almanac.inverse().get(almanac.get(seed)), seed);
+        }
+    }
+
+    #[test]
+    pub fn maps_intervals_across_segments() {
+        let map = RangeMap::new(vec![
+            Segment {
+                src: 10,
+                dst: 100,
+                len: 5,
+            },
+            Segment {
+                src: 20,
+                dst: 0,
+                len: 5,
+            },
+        ]);
+        assert_eq!(
+            map.get_interval(8, 15),
+            vec![(8, 2), (100, 5), (15, 5), (0, 3)]
+        );
+        let shift = RangeMap::new(vec![Segment {
+            src: 0,
+            dst: 50,
+            len: 200,
+        }]);
+        let both = compose(&map, &shift);
+        assert_eq!(both.get(12), 152);
+        assert_eq!(both.get(22), 52);
+        assert_eq!(both.get(300), 300);
+    }
+
+    const EXAMPLE: &str = "seeds: 79 14 55 13
 
-        assert_eq!(solve_part2(&maps), 72263011);
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    #[test]
+    pub fn queries_between_any_categories() {
+        let almanac = read_input(EXAMPLE);
+        assert_eq!(almanac.map("seed", "soil").unwrap().get(79), 81);
+        assert_eq!(almanac.map("water", "humidity").unwrap().get(81), 78);
+        assert_eq!(almanac.map("soil", "location").unwrap().get(81), 82);
+        assert_eq!(
+            almanac.map("location", "seed"),
+            Err(AlmanacError::NoPath {
+                from: "location".to_string(),
+                to: "seed".to_string()
+            })
+        );
+    }
+
+    #[test]
+    pub fn sections_can_come_in_any_order() {
+        let mut sections = EXAMPLE.split("\n\n").collect::<Vec<_>>();
+        sections[1..].reverse();
+        let shuffled = read_input(&sections.join("\n\n"));
+        assert_eq!(shuffled, read_input(EXAMPLE));
+    }
+
+    #[test]
+    pub fn rejects_invalid_almanacs() {
+        let broken = EXAMPLE.replace("water-to-light", "water-to-lamp");
+        assert_eq!(
+            Almanac::parse(&broken),
+            Err(AlmanacError::BrokenChain("lamp".to_string()))
+        );
+        let overlapping = EXAMPLE.replace("52 50 48", "52 50 49");
+        assert!(matches!(
+            Almanac::parse(&overlapping),
+            Err(AlmanacError::OverlappingRanges { from, .. }) if from == "seed"
+        ));
+        let bad_header = EXAMPLE.replace("seed-to-soil map:", "seed to soil:");
+        assert_eq!(
+            Almanac::parse(&bad_header),
+            Err(AlmanacError::InvalidHeader("seed to soil:".to_string()))
+        );
+        for range in ["18446744073709551615 0 2", "0 18446744073709551615 2"] {
+            let overflowing = EXAMPLE.replace("50 98 2", range);
+            assert_eq!(
+                Almanac::parse(&overflowing),
+                Err(AlmanacError::InvalidRange(range.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    pub fn finds_seeds_for_a_location() {
+        let almanac = read_input(EXAMPLE).map("seed", "location").unwrap();
+        assert_eq!(almanac.preimage(82), vec![79]);
+        assert!(almanac.preimage(46).contains(&82));
+        for location in 0..200 {
+            for seed in almanac.preimage(location) {
+                assert_eq!(almanac.get(seed), location);
+            }
+        }
+    }
+
+    #[test]
+    pub fn reverse_search_agrees_with_forward_search() {
+        let example = read_input(EXAMPLE);
+        assert_eq!(solve_part2_reverse(&example), solve_part2(&example));
+        let input = read_input(include_str!("../input/2023/day5.txt"));
+        assert_eq!(solve_part2_reverse(&input), solve_part2(&input));
     }
 }
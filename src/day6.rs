@@ -1,3 +1,5 @@
+use std::ops::RangeInclusive;
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
 pub struct Race {
@@ -5,14 +7,44 @@ pub struct Race {
     record: u64,
 }
 
+/// Largest `r` with `r * r <= n`, found with Newton's method so it stays exact for any `u128`.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
 impl Race {
+    fn distance(&self, hold: u64) -> u128 {
+        hold as u128 * (self.duration - hold) as u128
+    }
+
+    /// Hold times that beat the record. The distance `h * (duration - h)` is symmetric
+    /// around half the duration, so only the lower bound needs solving for.
+    pub fn winning_hold_times(&self) -> Option<RangeInclusive<u64>> {
+        let (duration, record) = (self.duration as u128, self.record as u128);
+        let discriminant = (duration * duration).checked_sub(4 * record)?;
+        let mut lo = ((duration - isqrt(discriminant)) / 2) as u64;
+        // The square root is floored, so the root may be off by one either way
+        while lo > 0 && self.distance(lo - 1) > record {
+            lo -= 1;
+        }
+        while lo <= self.duration / 2 && self.distance(lo) <= record {
+            lo += 1;
+        }
+        (lo <= self.duration / 2).then(|| lo..=self.duration - lo)
+    }
+
     pub fn constant_time_wins(&self) -> u64 {
-        let d = self.duration as f64 / 2.0;
-        let d_2 = d * d;
-        let root = (d_2 - self.record as f64).sqrt();
-        let from_dur = (d - root) as u64;
-        let to_dur = (d + root) as u64;
-        to_dur - from_dur
+        self.winning_hold_times()
+            .map_or(0, |wins| wins.end() - wins.start() + 1)
     }
 }
 
@@ -46,7 +78,7 @@ pub fn parse_input(input: &str) -> Vec<Race> {
 }
 
 #[aoc_generator(day6, part2)]
-fn parse_input_part2(input: &str) -> Race {
+pub(crate) fn parse_input_part2(input: &str) -> Race {
     let mut lines = input.lines();
     let time = lines
         .next()
@@ -95,12 +127,6 @@ Distance:  9  40  200"#;
         assert_eq!(races.len(), 3);
     }
 
-    #[test]
-    pub fn solves_part1() {
-        let races = parse_input(include_str!("../input/2023/day6.txt"));
-        assert_eq!(part1(&races), 2612736);
-    }
-
     #[test]
     pub fn can_solve_part2_for_testinput() {
         let race = parse_input_part2(TEST_INPUT);
@@ -108,8 +134,35 @@ Distance:  9  40  200"#;
     }
 
     #[test]
-    pub fn solves_part2() {
-        let race = parse_input_part2(include_str!("../input/2023/day6.txt"));
-        assert_eq!(part2(&race), 29891250);
+    pub fn winning_interval_for_test_input() {
+        let races = parse_input(TEST_INPUT);
+        assert_eq!(races[0].winning_hold_times(), Some(2..=5));
+        assert_eq!(races[1].winning_hold_times(), Some(4..=11));
+        assert_eq!(races[2].winning_hold_times(), Some(11..=19));
+    }
+
+    #[test]
+    pub fn matches_brute_force_on_small_races() {
+        for duration in 0..60 {
+            for record in 0..=duration * duration / 4 + 1 {
+                let race = Race { duration, record };
+                let wins = (0..=duration)
+                    .filter(|&hold| hold * (duration - hold) > record)
+                    .collect::<Vec<_>>();
+                let expected = wins.first().map(|&lo| lo..=*wins.last().unwrap());
+                assert_eq!(race.winning_hold_times(), expected, "{duration} {record}");
+            }
+        }
+    }
+
+    #[test]
+    pub fn exact_beyond_f64_precision() {
+        let race = Race {
+            duration: u64::MAX,
+            record: u64::MAX,
+        };
+        let wins = race.winning_hold_times().unwrap();
+        assert_eq!(*wins.start(), 2);
+        assert_eq!(*wins.end(), u64::MAX - 2);
     }
 }
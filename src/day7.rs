@@ -1,83 +1,5 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::HashMap;
-use std::fmt::Debug;
-use std::hash::Hash;
-
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
-enum CamelCard {
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-impl From<&char> for CamelCard {
-    fn from(value: &char) -> Self {
-        match value {
-            '2' => CamelCard::Two,
-            '3' => CamelCard::Three,
-            '4' => CamelCard::Four,
-            '5' => CamelCard::Five,
-            '6' => CamelCard::Six,
-            '7' => CamelCard::Seven,
-            '8' => CamelCard::Eight,
-            '9' => CamelCard::Nine,
-            'T' => CamelCard::Ten,
-            'J' => CamelCard::Jack,
-            'Q' => CamelCard::Queen,
-            'K' => CamelCard::King,
-            'A' => CamelCard::Ace,
-            _ => panic!("Invalid card value"),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
-enum JokerCamelCard {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Queen,
-    King,
-    Ace,
-}
-
-impl From<&char> for JokerCamelCard {
-    fn from(value: &char) -> Self {
-        match value {
-            '2' => JokerCamelCard::Two,
-            '3' => JokerCamelCard::Three,
-            '4' => JokerCamelCard::Four,
-            '5' => JokerCamelCard::Five,
-            '6' => JokerCamelCard::Six,
-            '7' => JokerCamelCard::Seven,
-            '8' => JokerCamelCard::Eight,
-            '9' => JokerCamelCard::Nine,
-            'T' => JokerCamelCard::Ten,
-            'J' => JokerCamelCard::Joker,
-            'Q' => JokerCamelCard::Queen,
-            'K' => JokerCamelCard::King,
-            'A' => JokerCamelCard::Ace,
-            _ => panic!("Invalid card value"),
-        }
-    }
-}
 
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum CamelHandType {
@@ -90,155 +12,219 @@ pub enum CamelHandType {
     FiveOfAKind,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Hand<C>
-where
-    C: Debug + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Hash,
-{
-    cards: [C; 5],
-}
+/// The sizes of the groups of equal cards in a hand, largest first. Comparing these
+/// lexicographically orders hands the usual poker way for any hand size.
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct HandType(Vec<usize>);
 
-impl<C> PartialOrd for Hand<C>
-where
-    C: Debug + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Hash,
-    CamelHandType: From<Self>,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl HandType {
+    /// The Camel Cards name for a five card hand type.
+    pub fn camel(&self) -> Option<CamelHandType> {
+        match self.0[..] {
+            [1, 1, 1, 1, 1] => Some(CamelHandType::HighCard),
+            [2, 1, 1, 1] => Some(CamelHandType::OnePair),
+            [2, 2, 1] => Some(CamelHandType::TwoPair),
+            [3, 1, 1] => Some(CamelHandType::ThreeOfAKind),
+            [3, 2] => Some(CamelHandType::FullHouse),
+            [4, 1] => Some(CamelHandType::FourOfAKind),
+            [5] => Some(CamelHandType::FiveOfAKind),
+            _ => None,
+        }
     }
 }
 
-impl<C> Ord for Hand<C>
-where
-    C: Debug + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Hash,
-    CamelHandType: From<Self>,
-{
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let self_hand_type: CamelHandType = (*self).into();
-        let other_type: CamelHandType = (*other).into();
-        (self_hand_type, self.cards).cmp(&(other_type, other.cards))
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardError {
+    UnknownCard(char),
+    WrongHandSize { expected: usize, found: usize },
+    InvalidBid(String),
+    NoCards,
+    DuplicateCard(char),
 }
 
-impl From<Hand<CamelCard>> for CamelHandType {
-    fn from(value: Hand<CamelCard>) -> Self {
-        let mut map: HashMap<CamelCard, u8> = HashMap::with_capacity(5);
-        for card in value.cards.iter().copied() {
-            map.entry(card).and_modify(|c| *c += 1).or_insert(1);
-        }
-        let highest = map.values().max().unwrap();
-        match (map.len(), highest) {
-            (5, 1) => CamelHandType::HighCard,
-            (4, 2) => CamelHandType::OnePair,
-            (3, 2) => CamelHandType::TwoPair,
-            (3, 3) => CamelHandType::ThreeOfAKind,
-            (2, 3) => CamelHandType::FullHouse,
-            (2, 4) => CamelHandType::FourOfAKind,
-            (1, 5) => CamelHandType::FiveOfAKind,
-            _ => unreachable!("Invalid hand type"),
+impl std::fmt::Display for CardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardError::UnknownCard(card) => write!(f, "unknown card {card:?}"),
+            CardError::WrongHandSize { expected, found } => {
+                write!(f, "expected {expected} cards in a hand, found {found}")
+            }
+            CardError::InvalidBid(bid) => write!(f, "invalid bid {bid:?}"),
+            CardError::NoCards => write!(f, "rules must rank at least one card"),
+            CardError::DuplicateCard(card) => write!(f, "card {card:?} is ranked twice"),
         }
     }
 }
 
-impl From<Hand<JokerCamelCard>> for CamelHandType {
-    fn from(value: Hand<JokerCamelCard>) -> Self {
-        let mut map: HashMap<JokerCamelCard, u8> = HashMap::with_capacity(5);
-        for card in value.cards.iter().copied() {
-            map.entry(card).and_modify(|c| *c += 1).or_insert(1);
+/// Which cards exist and how they rank, which one (if any) is wild, and how many make a hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    order: Vec<char>,
+    wild: Option<usize>,
+    hand_size: usize,
+}
+
+impl Rules {
+    /// `order` lists the cards from weakest to strongest. The wild card keeps its place
+    /// in `order` when breaking ties between hands of the same type.
+    pub fn new(order: &str, wild: Option<char>, hand_size: usize) -> Result<Rules, CardError> {
+        let order = order.chars().collect::<Vec<_>>();
+        if order.is_empty() {
+            return Err(CardError::NoCards);
         }
-        let highest_non_joker = map
+        if let Some(&card) = order
             .iter()
-            .filter_map(|(&k, &v)| match k {
-                JokerCamelCard::Joker => None,
-                _ => Some(v),
+            .enumerate()
+            .find_map(|(i, c)| order[..i].contains(c).then_some(c))
+        {
+            return Err(CardError::DuplicateCard(card));
+        }
+        let wild = wild
+            .map(|w| {
+                order
+                    .iter()
+                    .position(|&c| c == w)
+                    .ok_or(CardError::UnknownCard(w))
             })
-            .max()
-            .unwrap_or(0);
-        let joker_count = map.get(&JokerCamelCard::Joker).copied().unwrap_or(0);
-        let highest = highest_non_joker + joker_count;
-        let mut len = map.len();
-        if joker_count > 0 && highest_non_joker > 0 {
-            len -= 1;
+            .transpose()?;
+        Ok(Rules {
+            order,
+            wild,
+            hand_size,
+        })
+    }
+
+    pub fn camel() -> Rules {
+        Rules::new("23456789TJQKA", None, 5).unwrap()
+    }
+
+    pub fn camel_with_jokers() -> Rules {
+        Rules::new("J23456789TQKA", Some('J'), 5).unwrap()
+    }
+
+    fn rank(&self, card: char) -> Result<usize, CardError> {
+        self.order
+            .iter()
+            .position(|&c| c == card)
+            .ok_or(CardError::UnknownCard(card))
+    }
+
+    pub fn hand(&self, cards: &str) -> Result<Hand, CardError> {
+        let cards = cards
+            .chars()
+            .map(|c| self.rank(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        if cards.len() != self.hand_size {
+            return Err(CardError::WrongHandSize {
+                expected: self.hand_size,
+                found: cards.len(),
+            });
         }
-        match (len, highest) {
-            (5, 1) => CamelHandType::HighCard,
-            (4, 2) => CamelHandType::OnePair,
-            (3, 2) => CamelHandType::TwoPair,
-            (3, 3) => CamelHandType::ThreeOfAKind,
-            (2, 3) => CamelHandType::FullHouse,
-            (2, 4) => CamelHandType::FourOfAKind,
-            (1, 5) => CamelHandType::FiveOfAKind,
-            _ => unreachable!("Invalid hand type"),
+        let (_, hand_type) = self.substitute(&cards);
+        Ok(Hand { hand_type, cards })
+    }
+
+    /// Replaces every wild card with the card that makes the strongest hand type.
+    fn substitute(&self, cards: &[usize]) -> (Vec<usize>, HandType) {
+        let mut counts: HashMap<usize, usize> = HashMap::with_capacity(cards.len());
+        for &card in cards {
+            *counts.entry(card).or_default() += 1;
         }
+        let wilds = self.wild.and_then(|w| counts.remove(&w)).unwrap_or(0);
+        // Backing the largest group is always best; among equals, prefer the stronger card
+        let target = counts
+            .iter()
+            .max_by_key(|&(&card, &count)| (count, card))
+            .map(|(&card, _)| card)
+            .unwrap_or(self.order.len() - 1);
+        *counts.entry(target).or_default() += wilds;
+        let mut groups = counts.into_values().collect::<Vec<_>>();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        let substituted = cards
+            .iter()
+            .map(|&card| {
+                if Some(card) == self.wild {
+                    target
+                } else {
+                    card
+                }
+            })
+            .collect();
+        (substituted, HandType(groups))
+    }
+
+    /// The concrete hand a wild hand plays as, and the type that gives it.
+    pub fn best_substitution(&self, hand: &Hand) -> (String, HandType) {
+        let (cards, hand_type) = self.substitute(&hand.cards);
+        (
+            cards.into_iter().map(|c| self.order[c]).collect(),
+            hand_type,
+        )
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Play<C>
-where
-    C: Debug + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Hash,
-    Hand<C>: PartialOrd + Ord,
-{
-    hand: Hand<C>,
+/// A hand of cards as ranks under some `Rules`, ordered by type and then card by card.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hand {
+    hand_type: HandType,
+    cards: Vec<usize>,
+}
+
+impl Hand {
+    pub fn hand_type(&self) -> &HandType {
+        &self.hand_type
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Play {
+    hand: Hand,
     bid: u128,
 }
-#[aoc_generator(day7, part1)]
-fn parse(input: &str) -> Vec<Play<CamelCard>> {
+
+fn parse_plays(input: &str, rules: &Rules) -> Result<Vec<Play>, CardError> {
     input
         .lines()
-        .map(|p| {
-            let cards = p
-                .split(' ').next()
-                .unwrap()
-                .chars()
-                .map(|c| CamelCard::from(&c))
-                .collect::<Vec<CamelCard>>();
-            let hand = Hand {
-                cards: [cards[0], cards[1], cards[2], cards[3], cards[4]],
-            };
-            let bid = p.split(' ').nth(1).unwrap().parse::<u128>().unwrap();
-            Play { hand, bid }
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (cards, bid) = line.trim().split_once(' ').unwrap_or((line, ""));
+            let hand = rules.hand(cards)?;
+            let bid = bid
+                .trim()
+                .parse::<u128>()
+                .map_err(|_| CardError::InvalidBid(bid.to_string()))?;
+            Ok(Play { hand, bid })
         })
         .collect()
 }
 
+#[aoc_generator(day7, part1)]
+pub(crate) fn parse(input: &str) -> Vec<Play> {
+    parse_plays(input, &Rules::camel()).unwrap_or_else(|e| panic!("Invalid play: {e}"))
+}
+
 #[aoc_generator(day7, part2)]
-fn parse_with_jokers(input: &str) -> Vec<Play<JokerCamelCard>> {
-    input
-        .lines()
-        .map(|p| {
-            let cards = p
-                .split(' ').next()
-                .unwrap()
-                .chars()
-                .map(|c| JokerCamelCard::from(&c))
-                .collect::<Vec<JokerCamelCard>>();
-            let hand = Hand {
-                cards: [cards[0], cards[1], cards[2], cards[3], cards[4]],
-            };
-            let bid = p.split(' ').nth(1).unwrap().parse::<u128>().unwrap();
-            Play { hand, bid }
-        })
-        .collect()
+pub(crate) fn parse_with_jokers(input: &str) -> Vec<Play> {
+    parse_plays(input, &Rules::camel_with_jokers()).unwrap_or_else(|e| panic!("Invalid play: {e}"))
 }
 
-#[aoc(day7, part1)]
-fn part1(input: &[Play<CamelCard>]) -> u128 {
+fn total_winnings(input: &[Play]) -> u128 {
     let mut plays = input.to_vec();
-    plays.sort_unstable_by_key(|p| p.hand);
+    plays.sort_unstable();
     plays
         .iter()
         .enumerate()
         .fold(0, |acc, (i, p)| acc + ((i as u128 + 1) * p.bid))
 }
+
+#[aoc(day7, part1)]
+pub(crate) fn part1(input: &[Play]) -> u128 {
+    total_winnings(input)
+}
+
 #[aoc(day7, part2)]
-fn part2(input: &[Play<JokerCamelCard>]) -> u128 {
-    let mut plays = input.to_vec();
-    plays.sort_unstable_by_key(|p| p.hand);
-    plays
-        .iter()
-        .enumerate()
-        .fold(0, |acc, (i, p)| acc + ((i as u128 + 1) * p.bid))
+pub(crate) fn part2(input: &[Play]) -> u128 {
+    total_winnings(input)
 }
 
 #[cfg(test)]
@@ -257,23 +243,46 @@ QQQJA 483"#;
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(
-            part1(&parse(include_str!("../input/2023/day7.txt"))),
-            249483956
-        );
+    fn part2_example() {
+        assert_eq!(part2(&parse_with_jokers(EXAMPLE)), 5905);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse_with_jokers(EXAMPLE)), 5905);
+    fn joker_substitution() {
+        let rules = Rules::camel_with_jokers();
+        let (cards, hand_type) = rules.best_substitution(&rules.hand("KTJJT").unwrap());
+        assert_eq!(cards, "KTTTT");
+        assert_eq!(hand_type.camel(), Some(CamelHandType::FourOfAKind));
+        let (cards, hand_type) = rules.best_substitution(&rules.hand("JJJJJ").unwrap());
+        assert_eq!(cards, "AAAAA");
+        assert_eq!(hand_type.camel(), Some(CamelHandType::FiveOfAKind));
+        assert!(rules.hand("JKKK2").unwrap() < rules.hand("QQQQ2").unwrap());
     }
 
     #[test]
-    fn solves_part2() {
+    fn custom_rules() {
+        let rules = Rules::new("123", Some('1'), 3).unwrap();
+        assert_eq!(
+            rules.hand("123").unwrap().hand_type(),
+            &HandType(vec![2, 1])
+        );
+        assert!(rules.hand("322").unwrap() < rules.hand("133").unwrap());
+        assert_eq!(rules.hand("1234"), Err(CardError::UnknownCard('4')));
+        assert_eq!(
+            rules.hand("12"),
+            Err(CardError::WrongHandSize {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Rules::new("123", Some('4'), 3),
+            Err(CardError::UnknownCard('4'))
+        );
+        assert_eq!(Rules::new("", None, 3), Err(CardError::NoCards));
         assert_eq!(
-            part2(&parse_with_jokers(include_str!("../input/2023/day7.txt"))),
-            252137472
+            Rules::new("1231", None, 3),
+            Err(CardError::DuplicateCard('1'))
         );
     }
 }
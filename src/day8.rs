@@ -6,7 +6,7 @@ use std::collections::HashMap;
 pub type Network = HashMap<String, (String, String)>;
 
 #[aoc_generator(day8)]
-fn parse(input: &str) -> (String, Network) {
+pub(crate) fn parse(input: &str) -> (String, Network) {
     let instructions = input.lines().next().unwrap().chars().collect();
     let pattern = Regex::new(r"(.+) = \((.+), (.+)\)").unwrap();
     let nodes = input
@@ -65,12 +65,12 @@ fn calculate_steps<'a>(
 }
 
 #[aoc(day8, part1)]
-fn part1((instructions, network): &(String, Network)) -> usize {
+pub(crate) fn part1((instructions, network): &(String, Network)) -> usize {
     walk_network(instructions, network, |n| n == "AAA", |n| n == "ZZZ")
 }
 
 #[aoc(day8, part2)]
-fn part2((instructions, network): &(String, Network)) -> usize {
+pub(crate) fn part2((instructions, network): &(String, Network)) -> usize {
     walk_network(
         instructions,
         network,
@@ -119,21 +119,8 @@ XXX = (XXX, XXX)"#;
         assert_eq!(part1(&parse(LOOPING_EXAMPLE)), 6);
     }
 
-    #[test]
-    fn solve_part1() {
-        assert_eq!(part1(&parse(include_str!("../input/2023/day8.txt"))), 18157);
-    }
-
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(GHOSTING_EXAMPLE)), 6);
     }
-
-    #[test]
-    fn solves_part2() {
-        assert_eq!(
-            part2(&parse(include_str!("../input/2023/day8.txt"))),
-            14299763833181
-        );
-    }
 }
@@ -1,49 +1,109 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use num::Zero;
-pub type Sequence = Vec<isize>;
-#[aoc_generator(day9)]
-fn parse(input: &str) -> Vec<Sequence> {
-    input
-        .lines()
-        .map(|l| l.split_whitespace().map(|n| n.parse().unwrap()).collect())
-        .collect()
-}
 
-fn find_next_values(mut sequence: Sequence) -> isize {
-    let mut last_numbers = vec![*sequence.last().unwrap()];
-    loop {
-        let new_sequence: Vec<_> = sequence.windows(2).map(|s| s[1] - s[0]).collect();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceError {
+    Empty,
+    /// The differences never settle to zero within the length of the sequence.
+    NotPolynomial,
+    Overflow,
+}
 
-        if new_sequence.iter().all(|n| n.is_zero()) {
-            break;
+impl std::fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceError::Empty => write!(f, "sequence has no values"),
+            SequenceError::NotPolynomial => {
+                write!(f, "sequence is not a polynomial within its length")
+            }
+            SequenceError::Overflow => write!(f, "value does not fit in an i128"),
         }
-        sequence = new_sequence;
-        last_numbers.push(*sequence.last().unwrap());
     }
-    last_numbers.iter().sum()
 }
 
-fn find_previous_values(mut sequence: Sequence) -> isize {
-    let mut first_numbers = vec![*sequence.first().unwrap()];
-    loop {
-        let new_sequence: Vec<_> = sequence.windows(2).map(|s| s[1] - s[0]).collect();
-        if new_sequence.iter().all(|n| n.is_zero()) {
-            break;
+/// A sequence of readings modelled as the polynomial through them, kept as the
+/// Newton forward differences at the first reading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    differences: Vec<i128>,
+}
+
+impl Sequence {
+    /// Needs one reading more than the degree to fit the polynomial, and another one
+    /// to confirm the differences have settled.
+    pub fn new(values: &[i128]) -> Result<Sequence, SequenceError> {
+        if values.is_empty() {
+            return Err(SequenceError::Empty);
         }
-        sequence = new_sequence;
-        first_numbers.push(*sequence.first().unwrap());
+        let mut row = values.to_vec();
+        let mut differences = vec![];
+        while row.iter().any(|n| *n != 0) {
+            if row.len() < 2 {
+                return Err(SequenceError::NotPolynomial);
+            }
+            differences.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|s| s[1].checked_sub(s[0]).ok_or(SequenceError::Overflow))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(Sequence { differences })
+    }
+
+    /// The degree of the polynomial, with the all-zero sequence counted as degree 0.
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// The value at index `n`, where the readings are at `0..len`. Negative indices
+    /// extrapolate backwards.
+    pub fn at(&self, n: i128) -> Result<i128, SequenceError> {
+        // Newton's forward formula, sum of Δᵏ·C(n, k), with C(n, k) built up term by term
+        let mut binomial: i128 = 1;
+        let mut value: i128 = 0;
+        for (k, difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                binomial = binomial
+                    .checked_mul(n - (k as i128 - 1))
+                    .ok_or(SequenceError::Overflow)?
+                    / k as i128;
+            }
+            value = difference
+                .checked_mul(binomial)
+                .and_then(|term| value.checked_add(term))
+                .ok_or(SequenceError::Overflow)?;
+        }
+        Ok(value)
     }
-    first_numbers.iter().rev().fold(0, |acc, n| n - acc)
+}
+
+#[aoc_generator(day9)]
+pub(crate) fn parse(input: &str) -> Vec<Vec<i128>> {
+    input
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.split_whitespace().map(|n| n.parse().unwrap()).collect())
+        .collect()
+}
+
+fn extrapolate(input: &[Vec<i128>], index: impl Fn(&[i128]) -> i128) -> i128 {
+    input
+        .iter()
+        .map(|values| {
+            Sequence::new(values)
+                .and_then(|sequence| sequence.at(index(values)))
+                .unwrap_or_else(|e| panic!("Cannot extrapolate {values:?}: {e}"))
+        })
+        .sum()
 }
 
 #[aoc(day9, part1)]
-fn part1(input: &[Sequence]) -> isize {
-    input.iter().map(|s| find_next_values(s.clone())).sum()
+pub(crate) fn part1(input: &[Vec<i128>]) -> i128 {
+    extrapolate(input, |values| values.len() as i128)
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &[Sequence]) -> isize {
-    input.iter().map(|s| find_previous_values(s.clone())).sum()
+pub(crate) fn part2(input: &[Vec<i128>]) -> i128 {
+    extrapolate(input, |_| -1)
 }
 
 #[cfg(test)]
@@ -60,20 +120,29 @@ mod tests {
     }
 
     #[test]
-    fn solves_part1() {
-        assert_eq!(
-            part1(&parse(include_str!("../input/2023/day9.txt"))),
-            2005352194
-        );
+    fn part2_example() {
+        assert_eq!(part2(&parse(SMALL_TEST)), 2);
     }
 
     #[test]
-    fn part2_example() {
-        assert_eq!(part2(&parse(SMALL_TEST)), 2);
+    fn evaluates_anywhere() {
+        let squares = Sequence::new(&[1, 4, 9, 16, 25]).unwrap();
+        assert_eq!(squares.degree(), 2);
+        assert_eq!(squares.at(-1), Ok(0));
+        assert_eq!(squares.at(-5), Ok(16));
+        assert_eq!(squares.at(999_999), Ok(1_000_000_000_000));
+        assert_eq!(Sequence::new(&[0, 0]).unwrap().degree(), 0);
+        assert_eq!(Sequence::new(&[7, 7]).unwrap().at(3), Ok(7));
     }
 
     #[test]
-    fn solves_part2() {
-        assert_eq!(part2(&parse(include_str!("../input/2023/day9.txt"))), 1077);
+    fn reports_non_polynomial_sequences() {
+        assert_eq!(
+            Sequence::new(&[1, 2, 4, 8, 16]),
+            Err(SequenceError::NotPolynomial)
+        );
+        assert_eq!(Sequence::new(&[]), Err(SequenceError::Empty));
+        let cubes = Sequence::new(&[0, 1, 8, 27, 64]).unwrap();
+        assert_eq!(cubes.at(i128::MAX / 2), Err(SequenceError::Overflow));
     }
 }
@@ -0,0 +1,95 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One puzzle input found under `input/<year>/`, named `dayN.txt` for the default
+/// account or `dayN-label.txt` for any other.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InputVariant {
+    pub day: u32,
+    pub label: Option<String>,
+    pub path: PathBuf,
+}
+
+impl InputVariant {
+    pub fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("day{}-{label}", self.day),
+            None => format!("day{}", self.day),
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+
+    /// Expected answers from the `.answers` file next to the input, with part 1 on the
+    /// first line and part 2 on the second. A missing file or blank line leaves that part
+    /// unchecked.
+    pub fn expected(&self) -> io::Result<[Option<String>; 2]> {
+        let contents = match fs::read_to_string(self.path.with_extension("answers")) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok([None, None]),
+            Err(e) => return Err(e),
+        };
+        let mut lines = contents
+            .lines()
+            .map(|l| Some(l.trim()).filter(|l| !l.is_empty()).map(str::to_owned));
+        Ok([lines.next().flatten(), lines.next().flatten()])
+    }
+}
+
+/// Splits `dayN.txt` or `dayN-label.txt` into the day and optional label.
+fn parse_file_name(name: &str) -> Option<(u32, Option<String>)> {
+    let stem = name.strip_suffix(".txt")?.strip_prefix("day")?;
+    let (day, label) = match stem.split_once('-') {
+        Some((day, label)) if !label.is_empty() => (day, Some(label.to_owned())),
+        Some(_) => return None,
+        None => (stem, None),
+    };
+    if day.is_empty() || !day.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((day.parse().ok()?, label))
+}
+
+/// Every input variant for `year` under `root`, sorted by day with the default input first.
+pub fn discover(root: &Path, year: u32) -> io::Result<Vec<InputVariant>> {
+    let mut variants = vec![];
+    for entry in fs::read_dir(root.join(year.to_string()))? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if let Some((day, label)) = parse_file_name(name) {
+            variants.push(InputVariant { day, label, path });
+        }
+    }
+    variants.sort();
+    Ok(variants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_variant_names() {
+        assert_eq!(parse_file_name("day7.txt"), Some((7, None)));
+        assert_eq!(
+            parse_file_name("day12-alice.txt"),
+            Some((12, Some("alice".to_string())))
+        );
+        assert_eq!(parse_file_name("day12-.txt"), None);
+        assert_eq!(parse_file_name("day12.answers"), None);
+        assert_eq!(parse_file_name("dayx.txt"), None);
+    }
+
+    #[test]
+    fn discovers_default_inputs() {
+        let variants = discover(Path::new("input"), 2023).unwrap();
+        assert_eq!(variants.iter().filter(|v| v.label.is_none()).count(), 25);
+        assert_eq!(variants[0].name(), "day1");
+        assert_eq!(variants.last().unwrap().day, 25);
+    }
+}
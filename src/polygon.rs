@@ -0,0 +1,117 @@
+use num::integer::gcd;
+use num::rational::Ratio;
+
+pub type Vertex = (i64, i64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    Degenerate,
+}
+
+/// A closed lattice polygon. The last vertex is implicitly connected back to the first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Polygon {
+    pub vertices: Vec<Vertex>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Vertex>) -> Polygon {
+        Polygon { vertices }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vertex, Vertex)> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// Twice the signed area from the shoelace formula, positive for counter-clockwise
+    /// vertices in a y-up coordinate system.
+    pub fn signed_double_area(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| a.0 as i128 * b.1 as i128 - a.1 as i128 * b.0 as i128)
+            .sum()
+    }
+
+    /// The exact signed area, which is a half-integer for some lattice polygons.
+    pub fn signed_area(&self) -> Ratio<i128> {
+        Ratio::new(self.signed_double_area(), 2)
+    }
+
+    /// The absolute area rounded down, exact whenever the doubled area is even.
+    pub fn area(&self) -> i128 {
+        self.signed_double_area().abs() / 2
+    }
+
+    /// Number of lattice points on the boundary, which for rectilinear polygons is the perimeter.
+    pub fn boundary_points(&self) -> i128 {
+        self.edges()
+            .map(|(a, b)| gcd(b.0 as i128 - a.0 as i128, b.1 as i128 - a.1 as i128).abs())
+            .sum()
+    }
+
+    /// Number of lattice points strictly inside the polygon, using Pick's theorem.
+    ///
+    /// Pick's theorem only holds for simple polygons that enclose some area, so this is `None`
+    /// for degenerate polygons. Self-intersecting polygons are not detected.
+    pub fn interior_points(&self) -> Option<i128> {
+        if self.orientation() == Orientation::Degenerate {
+            return None;
+        }
+        // Pick's theorem gives I = A - B/2 + 1, which is an integer, so 2A and B have the same
+        // parity and flooring both halves drops the same 1/2 from each side.
+        Some(self.area() - self.boundary_points() / 2 + 1)
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        match self.signed_double_area().signum() {
+            1 => Orientation::CounterClockwise,
+            -1 => Orientation::Clockwise,
+            _ => Orientation::Degenerate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square() {
+        let square = Polygon::new(vec![(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(square.area(), 16);
+        assert_eq!(square.boundary_points(), 16);
+        assert_eq!(square.interior_points(), Some(9));
+        assert_eq!(square.orientation(), Orientation::CounterClockwise);
+    }
+
+    #[test]
+    fn reversed_is_clockwise() {
+        let triangle = Polygon::new(vec![(0, 0), (0, 3), (3, 0)]);
+        assert_eq!(triangle.orientation(), Orientation::Clockwise);
+        assert_eq!(triangle.signed_double_area(), -9);
+        assert_eq!(triangle.boundary_points(), 9);
+        assert_eq!(triangle.interior_points(), Some(1));
+        assert_eq!(triangle.signed_area(), Ratio::new(-9, 2));
+        assert_eq!(triangle.area(), 4);
+    }
+
+    #[test]
+    fn coordinates_beyond_i32() {
+        let big = 3_000_000_000_i64;
+        let square = Polygon::new(vec![(0, 0), (big, 0), (big, big), (0, big)]);
+        assert_eq!(square.area(), big as i128 * big as i128);
+        assert_eq!(square.boundary_points(), 4 * big as i128);
+    }
+
+    #[test]
+    fn degenerate() {
+        let line = Polygon::new(vec![(0, 0), (5, 0)]);
+        assert_eq!(line.orientation(), Orientation::Degenerate);
+        assert_eq!(line.area(), 0);
+        assert_eq!(line.interior_points(), None);
+    }
+}
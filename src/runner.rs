@@ -0,0 +1,246 @@
+use crate::inputs::{discover, InputVariant};
+use crate::*;
+use std::io;
+use std::panic::catch_unwind;
+use std::path::Path;
+
+/// One part of one day, taking the raw input and returning the answer as text.
+#[derive(Debug, Copy, Clone)]
+pub struct Solver {
+    pub day: u32,
+    pub part: u8,
+    pub solve: fn(&str) -> String,
+}
+
+macro_rules! solver {
+    ($day:literal, $part:literal, $generator:path, $solve:path) => {
+        Solver {
+            day: $day,
+            part: $part,
+            solve: |input| $solve(&$generator(input)).to_string(),
+        }
+    };
+    ($day:literal, $part:literal, $solve:path) => {
+        Solver {
+            day: $day,
+            part: $part,
+            solve: |input| $solve(input).to_string(),
+        }
+    };
+}
+
+pub fn solvers() -> Vec<Solver> {
+    vec![
+        solver!(1, 1, day1::input_generator_part_1, day1::solve_part1),
+        solver!(1, 2, day1::input_generator_part_2, day1::solve_part2),
+        solver!(2, 1, day2::generator, day2::solve_part1),
+        solver!(2, 2, day2::generator, day2::solve_part2),
+        solver!(3, 1, day3::input_generator, day3::solve_part1),
+        solver!(3, 2, day3::input_generator, day3::solve_part2),
+        solver!(4, 1, day4::generator, day4::solve_part1),
+        solver!(4, 2, day4::generator, day4::solve_part2),
+        solver!(5, 1, day5::read_input, day5::solve_part1),
+        solver!(5, 2, day5::read_input, day5::solve_part2),
+        solver!(6, 1, day6::parse_input, day6::part1),
+        solver!(6, 2, day6::parse_input_part2, day6::part2),
+        solver!(7, 1, day7::parse, day7::part1),
+        solver!(7, 2, day7::parse_with_jokers, day7::part2),
+        solver!(8, 1, day8::parse, day8::part1),
+        solver!(8, 2, day8::parse, day8::part2),
+        solver!(9, 1, day9::parse, day9::part1),
+        solver!(9, 2, day9::parse, day9::part2),
+        solver!(10, 1, day10::parse, day10::part1),
+        solver!(10, 2, day10::parse, day10::part2),
+        solver!(11, 1, day11::parse, day11::part1),
+        solver!(11, 2, day11::parse, day11::part2),
+        solver!(12, 1, day12::parse, day12::part1),
+        solver!(12, 2, day12::parse, day12::part2),
+        solver!(13, 1, day13::parse, day13::part1),
+        solver!(13, 2, day13::parse, day13::part2),
+        solver!(14, 1, day14::parse, day14::part1),
+        solver!(14, 2, day14::parse, day14::part2),
+        solver!(15, 1, day15::parse, day15::part1),
+        solver!(15, 2, day15::parse, day15::part2),
+        solver!(16, 1, day16::parse, day16::part1),
+        solver!(16, 2, day16::parse, day16::part2),
+        solver!(17, 1, day17::parse, day17::part1),
+        solver!(17, 2, day17::parse, day17::part2),
+        solver!(18, 1, day18::parse, day18::part1),
+        solver!(18, 2, day18::parse, day18::part2),
+        solver!(19, 1, day19::part1),
+        solver!(19, 2, day19::part2),
+        solver!(20, 1, day20::part1),
+        solver!(20, 2, day20::part2),
+        solver!(21, 1, day21::parse, day21::part1),
+        solver!(21, 2, day21::parse, day21::part2),
+        solver!(22, 1, day22::parse, day22::part1),
+        solver!(22, 2, day22::parse, day22::part2),
+        solver!(23, 1, day23::parse, day23::part1),
+        solver!(23, 2, day23::parse, day23::part2),
+        solver!(24, 1, day24::parse, day24::part1),
+        solver!(24, 2, day24::parse, day24::part2),
+        solver!(25, 1, day25::parse, day25::part1),
+        solver!(25, 2, day25::parse, day25::part2),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Unchecked,
+    Correct,
+    Incorrect {
+        expected: String,
+    },
+    /// The solver panicked, usually on input it could not parse.
+    Panicked {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub variant: String,
+    pub day: u32,
+    pub part: u8,
+    pub answer: String,
+    pub outcome: Outcome,
+}
+
+/// Runs one solver, turning a panic into its message so one bad input cannot stop a whole run.
+fn solve(solver: &Solver, input: &str) -> Result<String, String> {
+    catch_unwind(|| (solver.solve)(input)).map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    })
+}
+
+/// Runs both parts of the variant's day, checking them against its expected answers.
+pub fn run_variant(variant: &InputVariant) -> io::Result<Vec<Run>> {
+    let input = variant.read()?;
+    let expected = variant.expected()?;
+    Ok(solvers()
+        .into_iter()
+        .filter(|solver| solver.day == variant.day)
+        .map(|solver| {
+            let (answer, outcome) = match solve(&solver, input.trim_end()) {
+                Ok(answer) => {
+                    let outcome = check(&expected[solver.part as usize - 1], &answer);
+                    (answer, outcome)
+                }
+                Err(message) => (String::new(), Outcome::Panicked { message }),
+            };
+            Run {
+                variant: variant.name(),
+                day: solver.day,
+                part: solver.part,
+                answer,
+                outcome,
+            }
+        })
+        .collect())
+}
+
+fn check(expected: &Option<String>, answer: &str) -> Outcome {
+    match expected {
+        None => Outcome::Unchecked,
+        Some(expected) if expected == answer => Outcome::Correct,
+        Some(expected) => Outcome::Incorrect {
+            expected: expected.clone(),
+        },
+    }
+}
+
+/// Runs every solver against every input variant discovered for `year` under `root`.
+pub fn run_all(root: &Path, year: u32) -> io::Result<Vec<Run>> {
+    let mut runs = vec![];
+    for variant in discover(root, year)? {
+        runs.extend(run_variant(&variant)?);
+    }
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn default_inputs_have_answers() {
+        for variant in discover(Path::new("input"), 2023).unwrap() {
+            if variant.label.is_none() {
+                assert!(
+                    variant.expected().unwrap()[0].is_some(),
+                    "{}",
+                    variant.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn default_inputs_match_answers() {
+        for run in run_all(Path::new("input"), 2023).unwrap() {
+            assert!(
+                matches!(run.outcome, Outcome::Correct | Outcome::Unchecked),
+                "{} part {}: {:?}",
+                run.variant,
+                run.part,
+                run.outcome
+            );
+        }
+    }
+
+    #[test]
+    fn covers_every_part() {
+        let solvers = solvers();
+        for day in 1..=25 {
+            for part in 1..=2 {
+                assert!(solvers.iter().any(|s| s.day == day && s.part == part));
+            }
+        }
+    }
+
+    #[test]
+    fn checks_answers_per_variant() {
+        let root = std::env::temp_dir().join(format!("aocrs-runner-{}", std::process::id()));
+        let dir = root.join("2023");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("day9.txt"), "0 3 6 9 12 15\n").unwrap();
+        fs::write(dir.join("day9.answers"), "18\n").unwrap();
+        fs::write(dir.join("day9-bob.txt"), "1 3 6 10 15 21\n").unwrap();
+        fs::write(dir.join("day9-bob.answers"), "27\n0\n").unwrap();
+        fs::write(dir.join("day9-carol.txt"), "1 two 3\n").unwrap();
+
+        let runs = run_all(&root, 2023).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let (checked, malformed) = runs.split_at(4);
+        let outcomes: Vec<_> = checked
+            .iter()
+            .map(|r| (r.variant.as_str(), r.part, r.outcome.clone()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("day9", 1, Outcome::Correct),
+                ("day9", 2, Outcome::Unchecked),
+                (
+                    "day9-bob",
+                    1,
+                    Outcome::Incorrect {
+                        expected: "27".to_string()
+                    }
+                ),
+                ("day9-bob", 2, Outcome::Correct),
+            ]
+        );
+        assert_eq!(malformed.len(), 2);
+        for run in malformed {
+            assert_eq!(run.variant, "day9-carol");
+            assert!(matches!(run.outcome, Outcome::Panicked { .. }));
+        }
+    }
+}